@@ -0,0 +1,32 @@
+// Cost of supersampling, measured by rendering frames of the turning cube at each factor. Run
+// with `cargo bench --bench antialiasing`
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rs_cube::{Cube, Renderer, Scene, Theme};
+use std::hint::black_box;
+
+const SIZES: [(u16, u16); 2] = [(80, 24), (160, 50)];
+const FACTORS: [u16; 3] = [1, 2, 4];
+
+fn antialiasing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("antialiasing");
+
+    for (width, height) in SIZES {
+        for factor in FACTORS {
+            let mut renderer = Renderer::new(width, height, factor, Theme::default());
+            let mut scene = Scene::new(Cube::solved());
+            let id = BenchmarkId::new(format!("{}x{}", width, height), format!("aa {}", factor));
+            group.bench_function(id, |bencher| {
+                bencher.iter(|| {
+                    scene.angles.0 += 0.03;
+                    scene.angles.1 += 0.02;
+                    scene.angles.2 += 0.01;
+                    black_box(renderer.render(&scene));
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, antialiasing);
+criterion_main!(benches);
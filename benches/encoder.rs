@@ -0,0 +1,73 @@
+// Bytes written per frame by the escape code encoder, against positioning the cursor for every
+// changed cell and setting the colour whenever it differs from the last one. Run with
+// `cargo bench --bench encoder`
+use rs_cube::{cube, Cube, Renderer, Scene, Theme};
+use std::io::Write;
+
+const FRAMES: usize = 300;
+const FPS: usize = 60;
+const SIZES: [(u16, u16); 3] = [(80, 24), (120, 40), (200, 60)];
+
+// Cursor position before every changed cell, as frames were written before the encoder
+fn write_naive(out: &mut Vec<u8>, width: u16, cells: &[(char, &str)], prev: &[(char, &str)]) {
+    let mut prev_set_color = "";
+    for (index, (&(val, color), &prev)) in cells.iter().zip(prev).enumerate() {
+        if (val, color) == prev {
+            continue;
+        }
+        let (x, y) = (index % width as usize + 1, index / width as usize + 1);
+        write!(out, "\x1B[{};{}H", y, x).unwrap();
+        if color != prev_set_color {
+            write!(out, "{}", color).unwrap();
+            prev_set_color = color;
+        }
+        write!(out, "{}", val).unwrap();
+    }
+}
+
+fn main() {
+    println!(
+        "{} frames of a turning cube, bytes per frame and bandwidth at {} fps",
+        FRAMES, FPS
+    );
+    for (width, height) in SIZES {
+        let mut scene = Scene::new(Cube::solved());
+        let moves = cube::parse_alg("R U R' U'").unwrap();
+        let mut renderer = Renderer::new(width, height, 1, Theme::default());
+        let mut prev: Vec<(char, &str)> =
+            vec![(' ', Theme::default().blank); width as usize * height as usize];
+        let (mut naive, mut encoded) = (Vec::new(), Vec::new());
+
+        for frame_index in 0..FRAMES {
+            scene.angles.0 += 0.03;
+            scene.angles.1 += 0.02;
+            scene.angles.2 += 0.01;
+            if frame_index % 30 == 0 {
+                scene.cube.apply(&moves[frame_index / 30 % moves.len()]);
+            }
+            let frame = renderer.render(&scene);
+            let cells: Vec<(char, &str)> = frame
+                .chars
+                .iter()
+                .copied()
+                .zip(frame.colors.iter().copied())
+                .collect();
+            write_naive(&mut naive, width, &cells, &prev);
+            frame.write_changes(&mut encoded, true).unwrap();
+            prev = cells;
+        }
+
+        let per_frame = |bytes: &Vec<u8>| bytes.len() / FRAMES;
+        let kbits = |bytes: &Vec<u8>| per_frame(bytes) * FPS * 8 / 1000;
+        println!(
+            "{:>3}x{:<3} naive {:>6} B {:>6} kbit/s, encoded {:>6} B {:>6} kbit/s, {:>5.1}% saved",
+            width,
+            height,
+            per_frame(&naive),
+            kbits(&naive),
+            per_frame(&encoded),
+            kbits(&encoded),
+            100.0 - 100.0 * encoded.len() as f64 / naive.len() as f64
+        );
+    }
+}
@@ -0,0 +1,55 @@
+// Points rotated and projected per second by the batched transform, against projecting them one
+// call at a time as frames were drawn before it. Run with `cargo bench --bench transform`
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rs_cube::camera::Camera;
+use rs_cube::init::{self, Model};
+use rs_cube::transform::{Transform, Transformed};
+use std::hint::black_box;
+
+const SIZES: [(u16, u16); 3] = [(80, 24), (120, 40), (200, 60)];
+
+fn transform(c: &mut Criterion) {
+    let camera = Camera::default();
+    let (a, b, c_) = rs_cube::START_ANGLES;
+    let trig_values = [a.sin(), a.cos(), b.sin(), b.cos(), c_.sin(), c_.cos()];
+    let mut group = c.benchmark_group("transform");
+
+    for (width, height) in SIZES {
+        let mut model = Model::default();
+        init::init(&mut model, camera.point_spacing(width, height));
+        let (x, y, z) = (&model.points_x, &model.points_y, &model.points_z);
+        let size = format!("{}x{}", width, height);
+        group.throughput(Throughput::Elements(model.len() as u64));
+
+        group.bench_function(BenchmarkId::new("per point", &size), |bencher| {
+            let mut out = vec![(0.0, 0.0, 0.0); model.len()];
+            bencher.iter(|| {
+                for (index, out) in out.iter_mut().enumerate() {
+                    *out = rs_cube::project(
+                        x[index],
+                        y[index],
+                        z[index],
+                        width,
+                        height,
+                        &trig_values,
+                        &camera,
+                    );
+                }
+                black_box(&out);
+            })
+        });
+
+        let transform = Transform::new(&trig_values, &camera, width, height);
+        let mut out = Transformed::default();
+        group.bench_function(BenchmarkId::new("scalar", &size), |bencher| {
+            bencher.iter(|| transform.apply_scalar(black_box(x), y, z, black_box(&mut out)))
+        });
+        group.bench_function(BenchmarkId::new("simd", &size), |bencher| {
+            bencher.iter(|| transform.apply(black_box(x), y, z, black_box(&mut out)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, transform);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+// A rotating cube inside a ratatui layout, next to a panel listing the moves it plays. Run
+// with `cargo run --example ratatui` and quit with q
+use ratatui::crossterm::event::{self, Event, KeyCode};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::widgets::{Block, List, ListItem, ListState};
+use rs_cube::{cube, Cube, CubeWidget, Renderer, Scene, Theme};
+use std::time::{Duration, Instant};
+
+const MOVES: &str = "R U R' U' R' F R2 U' R' U' R U R' F'";
+const MOVE_INTERVAL: Duration = Duration::from_millis(600);
+
+fn main() -> std::io::Result<()> {
+    let moves = cube::parse_alg(MOVES).expect("valid moves");
+    let mut scene = Scene::new(Cube::solved());
+    let mut renderer = Renderer::new(1, 1, 2, Theme::default());
+    let mut next_move = 0;
+    let mut last_move = Instant::now();
+
+    ratatui::run(|terminal| loop {
+        scene.angles.0 += 0.03;
+        scene.angles.1 += 0.02;
+        scene.angles.2 += 0.01;
+        if last_move.elapsed() >= MOVE_INTERVAL {
+            scene.cube.apply(&moves[next_move]);
+            next_move = (next_move + 1) % moves.len();
+            last_move = Instant::now();
+        }
+
+        terminal.draw(|frame| {
+            let [left, right] = Layout::horizontal([Constraint::Fill(1), Constraint::Length(16)])
+                .areas(frame.area());
+            let cube_block = Block::bordered().title(" rs-cube ");
+            let cube_area = cube_block.inner(left);
+            frame.render_widget(cube_block, left);
+            frame.render_stateful_widget(CubeWidget::new(&scene), cube_area, &mut renderer);
+
+            let items = MOVES.split(' ').map(ListItem::new);
+            let list = List::new(items)
+                .block(Block::bordered().title(" T-Perm "))
+                .highlight_symbol("> ");
+            let mut state = ListState::default().with_selected(Some(next_move));
+            frame.render_stateful_widget(list, right, &mut state);
+        })?;
+
+        if event::poll(Duration::from_millis(16))? {
+            if let Event::Key(key) = event::read()? {
+                if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                    return Ok(());
+                }
+            }
+        }
+    })
+}
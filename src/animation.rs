@@ -0,0 +1,125 @@
+use crate::parse_angles;
+use rs_cube::START_ANGLES;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, FRAC_PI_6, PI};
+
+// Radians per second around each axis, the steps of 0.03, 0.02 and 0.01 per frame the cube
+// used to turn by at 60 fps
+const TUMBLE_SPEEDS: (f32, f32, f32) = (1.8, 1.2, 0.6);
+// Radians per second around the vertical axis
+const TURNTABLE_SPEED: f32 = 0.8;
+// The turntable is seen from above, tipping the top towards the camera
+const TURNTABLE_TILT: f32 = -FRAC_PI_6;
+
+type Matrix = [[f32; 3]; 3];
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Preset {
+    // Every angle keeps growing at its own speed
+    Tumble,
+    // Upright and spinning around the vertical axis
+    Turntable,
+}
+
+#[derive(clap::Args)]
+pub struct AnimationArgs {
+    /// Rotation: tumble turns the cube around all three axes, turntable spins it upright around the vertical axis
+    #[arg(long, value_enum, default_value_t = Preset::Tumble)]
+    rotation: Preset,
+
+    /// Factor for the rotation speed, 0 to hold the cube still
+    #[arg(long, default_value_t = 1.0)]
+    speed: f32,
+
+    /// Tumble speeds "a,b,c" around each axis in radians per second [default: 1.8,1.2,0.6]
+    #[arg(long, value_parser = parse_angles, allow_hyphen_values = true)]
+    axis_speeds: Option<(f32, f32, f32)>,
+
+    /// Rotation angles "a,b,c" of the first frame in radians
+    #[arg(long, value_parser = parse_angles, allow_hyphen_values = true)]
+    start_angles: Option<(f32, f32, f32)>,
+}
+
+// How the cube turns over time, independent of the frame rate
+pub struct Animation {
+    preset: Preset,
+    start: (f32, f32, f32),
+    speeds: (f32, f32, f32),
+}
+
+impl Animation {
+    pub fn from_args(args: &AnimationArgs) -> Result<Self, String> {
+        if !args.speed.is_finite() {
+            return Err("speed must be a number".to_string());
+        }
+        if args.rotation == Preset::Turntable && args.axis_speeds.is_some() {
+            return Err("--axis-speeds only applies to the tumble rotation".to_string());
+        }
+        // The turntable only spins around the vertical axis
+        let (a, b, c) = match args.rotation {
+            Preset::Tumble => args.axis_speeds.unwrap_or(TUMBLE_SPEEDS),
+            Preset::Turntable => (0.0, TURNTABLE_SPEED, 0.0),
+        };
+        let start = args.start_angles.unwrap_or(match args.rotation {
+            Preset::Tumble => START_ANGLES,
+            // Upright with a corner to the front
+            Preset::Turntable => (PI, -FRAC_PI_4, -FRAC_PI_2),
+        });
+        Ok(Animation {
+            preset: args.rotation,
+            start,
+            speeds: (a * args.speed, b * args.speed, c * args.speed),
+        })
+    }
+
+    // Rotation angles after `time` seconds
+    pub fn angles(&self, time: f32) -> (f32, f32, f32) {
+        let (a, b, c) = self.start;
+        match self.preset {
+            Preset::Tumble => (
+                a + self.speeds.0 * time,
+                b + self.speeds.1 * time,
+                c + self.speeds.2 * time,
+            ),
+            // The starting orientation is spun around the vertical axis, then tilted
+            Preset::Turntable => {
+                let spin = euler_matrix(0.0, self.speeds.1 * time, 0.0);
+                let tilt = euler_matrix(0.0, 0.0, TURNTABLE_TILT);
+                matrix_angles(&mul(&tilt, &mul(&spin, &euler_matrix(a, b, c))))
+            }
+        }
+    }
+}
+
+// Rotation matrix of the angles as `rs_cube::project` applies them, by c around x, then b
+// around y and a around z
+fn euler_matrix(a: f32, b: f32, c: f32) -> Matrix {
+    let (sin_a, cos_a) = a.sin_cos();
+    let (sin_b, cos_b) = b.sin_cos();
+    let (sin_c, cos_c) = c.sin_cos();
+    [
+        [
+            cos_a * cos_b,
+            cos_a * sin_b * sin_c - sin_a * cos_c,
+            cos_a * sin_b * cos_c + sin_a * sin_c,
+        ],
+        [
+            sin_a * cos_b,
+            sin_a * sin_b * sin_c + cos_a * cos_c,
+            sin_a * sin_b * cos_c - cos_a * sin_c,
+        ],
+        [-sin_b, cos_b * sin_c, cos_b * cos_c],
+    ]
+}
+
+// Angles of a rotation matrix, the inverse of `euler_matrix`
+fn matrix_angles(m: &Matrix) -> (f32, f32, f32) {
+    (
+        m[1][0].atan2(m[0][0]),
+        (-m[2][0]).clamp(-1.0, 1.0).asin(),
+        m[2][1].atan2(m[2][2]),
+    )
+}
+
+fn mul(l: &Matrix, r: &Matrix) -> Matrix {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| l[i][k] * r[k][j]).sum()))
+}
@@ -22,9 +22,19 @@ ANSI_escape_code!(EraseScreen, "\x1B[2J");
 ANSI_escape_code!(EraseCurrentLine, "\x1B[2K");
 ANSI_escape_code!(EraseLineStartToCursor, "\x1B[1K");
 
+// Graphics Modes
+ANSI_escape_code!(ReverseVideo, "\x1B[7m");
+ANSI_escape_code!(NoReverseVideo, "\x1B[27m");
+
 // Common Private Modes
 ANSI_escape_code!(EnableAltBuffer, "\x1B[?1049h");
 ANSI_escape_code!(DisableAltBuffer, "\x1B[?1049l");
+ANSI_escape_code!(BeginSynchronizedUpdate, "\x1B[?2026h");
+ANSI_escape_code!(EndSynchronizedUpdate, "\x1B[?2026l");
+
+// Queries
+ANSI_escape_code!(RequestSynchronizedUpdateMode, "\x1B[?2026$p");
+ANSI_escape_code!(RequestDeviceAttributes, "\x1B[c");
 
 pub struct SetCursorPos(pub u16, pub u16);
 
@@ -33,3 +43,15 @@ impl fmt::Display for SetCursorPos {
         write!(f, "\x1B[{};{}H", self.0, self.1)
     }
 }
+
+// Moves the cursor right by a number of columns, leaving out the count for one
+pub struct CursorForward(pub u16);
+
+impl fmt::Display for CursorForward {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            1 => write!(f, "\x1B[C"),
+            n => write!(f, "\x1B[{}C", n),
+        }
+    }
+}
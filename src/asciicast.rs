@@ -0,0 +1,422 @@
+use crate::terminal;
+use rs_cube::ansi_escape_code;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(clap::Args)]
+pub struct PlayArgs {
+    /// asciicast v2 recording to replay
+    file: PathBuf,
+
+    /// Playback speed multiplier
+    #[arg(short, long, default_value_t = 1.0, value_parser = parse_speed)]
+    speed: f64,
+
+    /// Seek to this many seconds into the recording before playing
+    #[arg(long, default_value_t = 0.0)]
+    from: f64,
+
+    /// Stop playing at this many seconds into the recording
+    #[arg(long)]
+    to: Option<f64>,
+
+    /// Cap pauses between frames to this many seconds
+    #[arg(short, long, value_parser = parse_idle_time_limit)]
+    idle_time_limit: Option<f64>,
+}
+
+fn parse_speed(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        _ => Err("speed must be a number above 0".to_string()),
+    }
+}
+
+fn parse_idle_time_limit(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(limit) if limit.is_finite() && limit >= 0.0 => Ok(limit),
+        _ => Err("idle time limit must be a number of seconds, 0 or more".to_string()),
+    }
+}
+
+// Forwards everything to `inner` and, when recording, writes each flushed chunk as an
+// asciicast v2 output event
+pub struct Recorder<W: Write> {
+    inner: W,
+    cast: Option<BufWriter<File>>,
+    start: Instant,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(inner: W, cast: Option<File>, width: u16, height: u16) -> io::Result<Self> {
+        let mut recorder = Recorder {
+            inner,
+            cast: cast.map(BufWriter::new),
+            start: Instant::now(),
+            pending: Vec::new(),
+        };
+
+        if let Some(cast) = &mut recorder.cast {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            let term = std::env::var("TERM").unwrap_or_default();
+            writeln!(
+                cast,
+                "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}, \"env\": {{\"TERM\": \"{}\"}}}}",
+                width,
+                height,
+                timestamp,
+                escape(&term)
+            )?;
+            write_event(
+                cast,
+                0.0,
+                &format!(
+                    "{}{}",
+                    ansi_escape_code::EraseScreen,
+                    ansi_escape_code::CursorInvisible
+                ),
+            )?;
+        }
+        Ok(recorder)
+    }
+}
+
+impl<W: Write> Write for Recorder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if self.cast.is_some() {
+            self.pending.extend_from_slice(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        if let Some(cast) = &mut self.cast {
+            if !self.pending.is_empty() {
+                let time = self.start.elapsed().as_secs_f64();
+                write_event(cast, time, &String::from_utf8_lossy(&self.pending))?;
+                self.pending.clear();
+            }
+            cast.flush()?;
+        }
+        Ok(())
+    }
+}
+
+fn write_event(w: &mut impl Write, time: f64, data: &str) -> io::Result<()> {
+    writeln!(w, "[{:.6}, \"o\", \"{}\"]", time, escape(data))
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 || c as u32 == 0x7F => {
+                out.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// Just enough JSON to read asciicast headers and event lines
+enum Json {
+    Literal, // true, false or null
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected '{}', found '{}'", expected, c)),
+            None => Err(format!("expected '{}', found end of line", expected)),
+        }
+    }
+
+    fn literal(&mut self, word: &str) -> Result<Json, String> {
+        for expected in word.chars() {
+            if self.chars.next() != Some(expected) {
+                return Err(format!("invalid literal, expected {}", word));
+            }
+        }
+        Ok(Json::Literal)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('n') => self.literal("null"),
+            Some('t') => self.literal("true"),
+            Some('f') => self.literal("false"),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.chars.next();
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.chars.next_if_eq(&']').is_none() {
+                    loop {
+                        items.push(self.value()?);
+                        self.skip_whitespace();
+                        match self.chars.next() {
+                            Some(',') => continue,
+                            Some(']') => break,
+                            _ => return Err("unterminated array".to_string()),
+                        }
+                    }
+                }
+                Ok(Json::Array(items))
+            }
+            Some('{') => {
+                self.chars.next();
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.chars.next_if_eq(&'}').is_none() {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(':')?;
+                        members.push((key, self.value()?));
+                        self.skip_whitespace();
+                        match self.chars.next() {
+                            Some(',') => continue,
+                            Some('}') => break,
+                            _ => return Err("unterminated object".to_string()),
+                        }
+                    }
+                }
+                Ok(Json::Object(members))
+            }
+            Some(c) if *c == '-' || c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| c.is_ascii_digit() || "+-.eE".contains(*c))
+                {
+                    number.push(c);
+                }
+                number
+                    .parse()
+                    .map(Json::Number)
+                    .map_err(|_| format!("invalid number {}", number))
+            }
+            Some(c) => Err(format!("unexpected character '{}'", c)),
+            None => Err("unexpected end of line".to_string()),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(out),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('u') => {
+                        let mut code = self.hex4()?;
+                        // Surrogate pair, a high surrogate followed by a low one
+                        if (0xD800..0xDC00).contains(&code) {
+                            self.expect('\\')?;
+                            self.expect('u')?;
+                            let low = self.hex4()?;
+                            if !(0xDC00..0xE000).contains(&low) {
+                                return Err(format!("unpaired surrogate \\u{:04X}", code));
+                            }
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        }
+                        match char::from_u32(code) {
+                            Some(c) => out.push(c),
+                            None => return Err(format!("unpaired surrogate \\u{:04X}", code)),
+                        }
+                    }
+                    Some(c) => out.push(c),
+                    None => return Err("unterminated string".to_string()),
+                },
+                Some(c) => out.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let hex: String = self.chars.by_ref().take(4).collect();
+        // from_str_radix alone would also take a sign
+        if hex.len() != 4 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(format!("invalid escape \\u{}", hex));
+        }
+        u32::from_str_radix(&hex, 16).map_err(|_| format!("invalid escape \\u{}", hex))
+    }
+}
+
+fn parse_json(line: &str) -> Result<Json, String> {
+    let mut parser = Parser {
+        chars: line.chars().peekable(),
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    match parser.chars.next() {
+        None => Ok(value),
+        Some(c) => Err(format!("trailing character '{}'", c)),
+    }
+}
+
+struct Event {
+    time: f64,
+    data: String,
+}
+
+fn read_cast(file: &PathBuf) -> Result<Vec<Event>, String> {
+    let file = File::open(file).map_err(|e| e.to_string())?;
+    let mut lines = BufReader::new(file).lines();
+
+    let header = lines
+        .next()
+        .ok_or("empty recording")?
+        .map_err(|e| e.to_string())?;
+    match parse_json(&header)? {
+        Json::Object(members)
+            if members.iter().any(|(key, value)| {
+                key == "version" && matches!(value, Json::Number(v) if *v == 2.0)
+            }) => {}
+        _ => return Err("not an asciicast v2 recording".to_string()),
+    }
+
+    let mut events = Vec::new();
+    for (number, line) in lines.enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let error = |msg: String| format!("line {}: {}", number + 2, msg);
+        match parse_json(&line).map_err(error)? {
+            Json::Array(items) => match &items[..] {
+                [Json::Number(time), Json::String(kind), Json::String(data)] => {
+                    // Input, marker and resize events are not replayed
+                    if kind == "o" {
+                        events.push(Event {
+                            time: *time,
+                            data: data.clone(),
+                        });
+                    }
+                }
+                _ => return Err(error("malformed event".to_string())),
+            },
+            _ => return Err(error("malformed event".to_string())),
+        }
+    }
+    Ok(events)
+}
+
+pub fn play(args: &PlayArgs) -> Result<(), String> {
+    let events = read_cast(&args.file).map_err(|e| format!("{}: {}", args.file.display(), e))?;
+
+    terminal::install_signal_handlers();
+    let guard = terminal::TerminalGuard::enter(None);
+
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+
+    let start = Instant::now();
+    let mut prev_time = args.from;
+    let mut elapsed = 0.0;
+
+    for event in &events {
+        if terminal::exit_requested() {
+            break;
+        }
+        if args.to.is_some_and(|to| event.time > to) {
+            break;
+        }
+
+        // Everything before the seek position is replayed instantly to rebuild the screen. Times
+        // going backwards play without a pause
+        if event.time > args.from {
+            let mut delay = (event.time - prev_time).max(0.0);
+            if let Some(limit) = args.idle_time_limit {
+                delay = delay.min(limit);
+            }
+            elapsed += delay / args.speed;
+            prev_time = event.time;
+
+            let deadline = start + Duration::from_secs_f64(elapsed);
+            let now = Instant::now();
+            if deadline > now {
+                handle.flush().map_err(|e| e.to_string())?;
+                std::thread::sleep(deadline - now);
+            }
+        }
+        handle
+            .write_all(event.data.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+
+    handle.flush().map_err(|e| e.to_string())?;
+    drop(handle);
+    drop(guard);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(json: &str) -> Result<String, String> {
+        match parse_json(json)? {
+            Json::String(s) => Ok(s),
+            _ => panic!("{} is not a string", json),
+        }
+    }
+
+    #[test]
+    fn unicode_escapes() {
+        assert_eq!(string(r#""\u00e9\u2500""#).unwrap(), "\u{e9}\u{2500}");
+        assert_eq!(string(r#""\uD83D\uDE00""#).unwrap(), "\u{1F600}");
+        assert_eq!(string(r#""\ud83d\ude00""#).unwrap(), "\u{1F600}");
+    }
+
+    #[test]
+    fn invalid_unicode_escapes() {
+        for json in [
+            r#""\uDE00""#,
+            r#""\uD83D""#,
+            r#""\uD83Dx""#,
+            r#""\uD83D\u0041""#,
+            r#""\uD83D\uD83D""#,
+            r#""\u+041""#,
+            r#""\u-041""#,
+            r#""\u12""#,
+            r#""\u12G4""#,
+        ] {
+            assert!(string(json).is_err(), "{} was accepted", json);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::animation::Animation;
+use rs_cube::{camera, cube, Renderer, Scene};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+// Animation time between frames, as at the default frame limit
+const FRAME_TIME: f32 = 1.0 / 60.0;
+
+#[derive(clap::Args)]
+pub struct BenchArgs {
+    /// Frame width in characters
+    #[arg(long, default_value_t = 120u16)]
+    width: u16,
+
+    /// Frame height in characters
+    #[arg(long, default_value_t = 40u16)]
+    height: u16,
+
+    /// Number of frames to render
+    #[arg(short = 'n', long, default_value_t = 1000u32)]
+    frames: u32,
+
+    /// How frames are written out
+    #[arg(long, value_enum, default_value_t = Mode::Color)]
+    mode: Mode,
+
+    /// Print the results as JSON
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+// The ways frames reach stdout in the interactive mode
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Mode {
+    // Changed cells with colours, as in a terminal
+    Color,
+    // Changed cells without colours, as with NO_COLOR
+    Mono,
+    // Whole frames as plain text, as when piped
+    Plain,
+}
+
+impl Mode {
+    fn name(self) -> &'static str {
+        match self {
+            Mode::Color => "color",
+            Mode::Mono => "mono",
+            Mode::Plain => "plain",
+        }
+    }
+}
+
+// Throws the frames away, counting their bytes
+struct NullWriter {
+    bytes: u64,
+}
+
+impl Write for NullWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Renders a rotating cube the way the interactive mode does, without a terminal or any frame
+// limit, and reports how long the frames took
+pub fn bench(
+    args: &BenchArgs,
+    cube: &cube::Cube,
+    animation: &Animation,
+    options: &crate::Args,
+) -> Result<(), String> {
+    if args.width == 0 || args.height == 0 || args.frames == 0 {
+        return Err("width, height and frames must be at least 1".to_string());
+    }
+    if !Renderer::fits(args.width, args.height, options.aa) {
+        return Err(format!(
+            "--aa {} is too large for {}x{} characters",
+            options.aa, args.width, args.height
+        ));
+    }
+    let mut renderer = Renderer::new(args.width, args.height, options.aa, options.theme.clone());
+    renderer.edges = options.edges;
+    renderer.labels = options.labels;
+    let mut scene = Scene {
+        camera: options.camera.camera(camera::DEFAULT_ASPECT),
+        lighting: options.lighting.lighting(&options.theme.faces),
+        ..Scene::new(cube.clone())
+    };
+
+    let mut out = NullWriter { bytes: 0 };
+    let mut frame_buf: Vec<u8> = Vec::new();
+    let mut frame_times: Vec<Duration> = Vec::with_capacity(args.frames as usize);
+
+    for frame_index in 0..args.frames {
+        let start = Instant::now();
+        scene.lighting.orbit(FRAME_TIME);
+        scene.angles = scene
+            .camera
+            .fixed_angles()
+            .unwrap_or_else(|| animation.angles(frame_index as f32 * FRAME_TIME));
+
+        let frame = renderer.render(&scene);
+        frame_buf.clear();
+        match args.mode {
+            Mode::Color => frame.write_changes(&mut frame_buf, true),
+            Mode::Mono => frame.write_changes(&mut frame_buf, false),
+            Mode::Plain => frame
+                .write_plain(&mut frame_buf, false)
+                .and_then(|_| writeln!(frame_buf)),
+        }
+        .and_then(|_| out.write_all(&frame_buf))
+        .map_err(|e| e.to_string())?;
+        frame_times.push(start.elapsed());
+    }
+
+    let total: Duration = frame_times.iter().sum();
+    frame_times.sort_unstable();
+    // Nearest rank, so the p99 of fewer than 100 frames is the slowest one
+    let percentile = |p: usize| frame_times[(frame_times.len() * p).div_ceil(100).max(1) - 1];
+    let us = |d: Duration| d.as_secs_f64() * 1e6;
+    let (min, median, p99) = (us(frame_times[0]), us(percentile(50)), us(percentile(99)));
+    let points = renderer.points();
+    let points_per_second = (points as u64 * args.frames as u64) as f64 / total.as_secs_f64();
+    let bytes_per_frame = out.bytes / args.frames as u64;
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    if args.json {
+        writeln!(
+            stdout,
+            "{{\"width\": {}, \"height\": {}, \"frames\": {}, \"mode\": \"{}\", \"aa\": {}, \
+             \"points\": {}, \"min_us\": {:.1}, \"median_us\": {:.1}, \"p99_us\": {:.1}, \
+             \"points_per_second\": {:.0}, \"bytes\": {}, \"bytes_per_frame\": {}}}",
+            args.width,
+            args.height,
+            args.frames,
+            args.mode.name(),
+            options.aa,
+            points,
+            min,
+            median,
+            p99,
+            points_per_second,
+            out.bytes,
+            bytes_per_frame
+        )
+    } else {
+        writeln!(
+            stdout,
+            "{}x{} {}, {} frames of {} points\n\
+             frame time  min {:.1}us  median {:.1}us  p99 {:.1}us\n\
+             points/s    {:.2}M\n\
+             bytes       {} ({} per frame)",
+            args.width,
+            args.height,
+            args.mode.name(),
+            args.frames,
+            points,
+            min,
+            median,
+            p99,
+            points_per_second / 1e6,
+            out.bytes,
+            bytes_per_frame
+        )
+    }
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,181 @@
+use crate::{Vector3f, CUBE_SIZE};
+use std::ops::RangeInclusive;
+
+// Isometric view with U on top and F and R facing the viewer equally
+pub const ISOMETRIC_ANGLES: (f32, f32, f32) = (
+    -std::f32::consts::FRAC_PI_6,
+    -2.526_112_9, // asin(1 / sqrt(3)) - PI
+    std::f32::consts::FRAC_PI_2 + std::f32::consts::FRAC_PI_4,
+);
+
+// Matches the original fixed projection, a camera 10 units away framing the cube in 3/8 of the width
+pub const DEFAULT_FOV: f32 = 26.0;
+pub const DEFAULT_DISTANCE: f32 = 10.0;
+pub const DEFAULT_ASPECT: f32 = 2.0;
+
+// Settings a camera can take, from the command line or by zooming
+pub const FOV_RANGE: RangeInclusive<f32> = 10.0..=120.0;
+pub const DISTANCE_RANGE: RangeInclusive<f32> = 2.0..=100.0;
+pub const ASPECT_RANGE: RangeInclusive<f32> = 0.5..=4.0;
+
+// Spacing between points in columns, small enough to leave no holes between them
+const POINT_SPACING: f32 = 0.71;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective,
+    Orthographic,
+    // Orthographic with the cube held still in the isometric view
+    Isometric,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub projection: Projection,
+    pub fov: f32,
+    pub distance: f32,
+    pub aspect: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            projection: Projection::Perspective,
+            fov: DEFAULT_FOV,
+            distance: DEFAULT_DISTANCE,
+            aspect: DEFAULT_ASPECT,
+        }
+    }
+}
+
+impl Camera {
+    // Panics unless each setting is within its range
+    pub fn new(projection: Projection, fov: f32, distance: f32, aspect: f32) -> Self {
+        for (name, value, range) in [
+            ("field of view", fov, FOV_RANGE),
+            ("distance", distance, DISTANCE_RANGE),
+            ("aspect", aspect, ASPECT_RANGE),
+        ] {
+            assert!(range.contains(&value), "{} {} is out of range", name, value);
+        }
+        Camera {
+            projection,
+            fov,
+            distance,
+            aspect,
+        }
+    }
+
+    // Rotation angles the camera holds the cube at, if any
+    pub fn fixed_angles(&self) -> Option<(f32, f32, f32)> {
+        match self.projection {
+            Projection::Isometric => Some(ISOMETRIC_ANGLES),
+            _ => None,
+        }
+    }
+
+    // Focal length in columns, fitting the field of view into the width or the height,
+    // whichever is smaller once cells are squared up
+    pub(crate) fn focal_length(&self, width: u16, height: u16) -> f32 {
+        let size = (width as f32).min(height as f32 * self.aspect);
+        size / 2.0 / (self.fov.to_radians() / 2.0).tan()
+    }
+
+    // Projects a rotated point onto the screen, returning (x, y, 1 / z)
+    pub fn project(&self, x: f32, y: f32, z: f32, width: u16, height: u16) -> (f32, f32, f32) {
+        let ooz: f32 = 1.0 / (z + self.distance); // "One over z"
+        let focal_length = self.focal_length(width, height);
+        let scale = match self.projection {
+            Projection::Perspective => focal_length * ooz,
+            // Orthographic views keep the size the cube's centre has in perspective
+            Projection::Orthographic | Projection::Isometric => focal_length / self.distance,
+        };
+        (
+            (width as f32) / 2.0 + scale * x,
+            (height as f32) / 2.0 - scale * y / self.aspect,
+            ooz,
+        )
+    }
+
+    // Unit vector from a rotated point towards the camera
+    pub fn view_direction(&self, position: &Vector3f) -> Vector3f {
+        let mut view = match self.projection {
+            Projection::Perspective => Vector3f {
+                x: -position.x,
+                y: -position.y,
+                z: -self.distance - position.z,
+            },
+            Projection::Orthographic | Projection::Isometric => Vector3f {
+                x: 0.0,
+                y: 0.0,
+                z: -1.0,
+            },
+        };
+        view.norm();
+        view
+    }
+
+    // Distance between sampled points on the cube so the closest surface is still covered
+    pub fn point_spacing(&self, width: u16, height: u16) -> f32 {
+        let nearest = match self.projection {
+            Projection::Perspective => self.distance - CUBE_SIZE * 3.0_f32.sqrt() / 2.0,
+            Projection::Orthographic | Projection::Isometric => self.distance,
+        };
+        let columns_per_unit = self.focal_length(width, height) / nearest;
+        (POINT_SPACING / columns_per_unit).max(0.5 / width as f32)
+    }
+
+    pub fn apply(&mut self, action: CameraAction) {
+        match action {
+            CameraAction::CycleProjection => {
+                self.projection = match self.projection {
+                    Projection::Perspective => Projection::Orthographic,
+                    Projection::Orthographic => Projection::Isometric,
+                    Projection::Isometric => Projection::Perspective,
+                }
+            }
+            CameraAction::NarrowFov => self.fov = (self.fov - 2.0).max(*FOV_RANGE.start()),
+            CameraAction::WidenFov => self.fov = (self.fov + 2.0).min(*FOV_RANGE.end()),
+            CameraAction::Closer => {
+                self.distance = (self.distance - 0.5).max(*DISTANCE_RANGE.start())
+            }
+            CameraAction::Farther => {
+                self.distance = (self.distance + 0.5).min(*DISTANCE_RANGE.end())
+            }
+            CameraAction::DecreaseAspect => {
+                self.aspect = (self.aspect - 0.1).max(*ASPECT_RANGE.start())
+            }
+            CameraAction::IncreaseAspect => {
+                self.aspect = (self.aspect + 0.1).min(*ASPECT_RANGE.end())
+            }
+        }
+    }
+}
+
+// Live camera adjustments
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CameraAction {
+    // Perspective, orthographic and isometric in turn
+    CycleProjection,
+    NarrowFov,
+    WidenFov,
+    Closer,
+    Farther,
+    DecreaseAspect,
+    IncreaseAspect,
+}
+
+impl CameraAction {
+    // Default keys of the camera actions
+    pub const KEYS: [(u8, CameraAction); 9] = [
+        (b'p', CameraAction::CycleProjection),
+        (b'+', CameraAction::NarrowFov),
+        (b'=', CameraAction::NarrowFov),
+        (b'-', CameraAction::WidenFov),
+        (b'_', CameraAction::WidenFov),
+        (b'[', CameraAction::Closer),
+        (b']', CameraAction::Farther),
+        (b'{', CameraAction::DecreaseAspect),
+        (b'}', CameraAction::IncreaseAspect),
+    ];
+}
@@ -25,3 +25,50 @@ pub const BOLD_WHITE: &str = "\x1B[97m";
 // pub const BOLD_CYAN: &str = "\x1B[1;36m";
 // pub const BOLD_WHITE: &str = "\x1B[1;37m";
 // pub const BOLD_BLACK: &str = "\x1B[1;30m";
+
+/// Approximate RGB value of a colour code, used when rasterising frames off-screen
+pub fn to_rgb(color: &str) -> [u8; 3] {
+    match color {
+        BLACK => [0x00, 0x00, 0x00],
+        RED => [0xCD, 0x00, 0x00],
+        GREEN => [0x00, 0xCD, 0x00],
+        YELLOW => [0xCD, 0xCD, 0x00],
+        BLUE => [0x00, 0x00, 0xEE],
+        MAGENTA => [0xCD, 0x00, 0xCD],
+        CYAN => [0x00, 0xCD, 0xCD],
+        WHITE => [0xE5, 0xE5, 0xE5],
+        BOLD_BLACK => [0x7F, 0x7F, 0x7F],
+        BOLD_RED => [0xFF, 0x00, 0x00],
+        BOLD_GREEN => [0x00, 0xFF, 0x00],
+        BOLD_YELLOW => [0xFF, 0xFF, 0x00],
+        BOLD_BLUE => [0x5C, 0x5C, 0xFF],
+        BOLD_MAGENTA => [0xFF, 0x00, 0xFF],
+        BOLD_CYAN => [0x00, 0xFF, 0xFF],
+        BOLD_WHITE => [0xFF, 0xFF, 0xFF],
+        _ => parse_true_color(color).unwrap_or([0xE5, 0xE5, 0xE5]),
+    }
+}
+
+/// 24-bit foreground colour code. Codes are built once per theme and leaked so they can be
+/// stored in frame buffers alongside the constants above
+pub fn true_color(rgb: [u8; 3]) -> &'static str {
+    let code = format!("\x1B[38;2;{};{};{}m", rgb[0], rgb[1], rgb[2]);
+    Box::leak(code.into_boxed_str())
+}
+
+/// 24-bit background colour code
+pub fn true_color_background(rgb: [u8; 3]) -> &'static str {
+    let code = format!("\x1B[48;2;{};{};{}m", rgb[0], rgb[1], rgb[2]);
+    Box::leak(code.into_boxed_str())
+}
+
+fn parse_true_color(color: &str) -> Option<[u8; 3]> {
+    let params = color.strip_prefix("\x1B[38;2;")?.strip_suffix('m')?;
+    let mut channels = params.split(';').map(|channel| channel.parse::<u8>());
+    let rgb = [
+        channels.next()?.ok()?,
+        channels.next()?.ok()?,
+        channels.next()?.ok()?,
+    ];
+    channels.next().is_none().then_some(rgb)
+}
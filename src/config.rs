@@ -0,0 +1,637 @@
+use crate::{keys, Args};
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, CommandFactory, FromArgMatches};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+
+// Environment variables are named after the options with this in front, RS_CUBE_FPS_LIMIT for
+// --fps-limit
+const ENV_PREFIX: &str = "RS_CUBE_";
+
+// Table of the config file binding keys to actions in the interactive mode
+const BINDINGS: &str = "bindings";
+
+// Tables of the config file and the options that go in them, all others go at the top
+const TABLES: [(&str, &[&str]); 3] = [
+    ("camera", &["projection", "fov", "distance", "aspect"]),
+    (
+        "lighting",
+        &[
+            "light",
+            "ambient",
+            "diffuse",
+            "specular",
+            "shininess",
+            "per-pixel",
+            "light-orbit",
+        ],
+    ),
+    (
+        "animation",
+        &["rotation", "speed", "axis-speeds", "start-angles"],
+    ),
+];
+
+#[derive(clap::Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    action: ConfigAction,
+}
+
+#[derive(clap::Subcommand)]
+enum ConfigAction {
+    /// Print the configuration in effect as a config file, noting where each value comes from
+    Dump,
+}
+
+// The options in effect and where they came from
+pub struct Config {
+    pub args: Args,
+    // Keys and actions from the bindings table
+    pub bindings: Vec<(String, String)>,
+    matches: ArgMatches,
+    path: Option<PathBuf>,
+    from_file: HashSet<String>,
+    // Options set by an environment variable, and its name
+    from_env: HashMap<String, String>,
+}
+
+// A `key = value` line of the config file
+struct Entry {
+    table: String,
+    key: String,
+    values: Vec<String>,
+    line: usize,
+}
+
+// Reads the options from the command line, the environment, the config file and the defaults,
+// each one overriding the ones after it. The error says what is wrong with any of them, or
+// holds the help or version that was asked for
+pub fn load() -> Result<Config, clap::Error> {
+    load_from(std::env::args_os().collect(), &|name| {
+        std::env::var_os(name)
+    })
+}
+
+fn load_from(
+    argv: Vec<OsString>,
+    env: &dyn Fn(&str) -> Option<OsString>,
+) -> Result<Config, clap::Error> {
+    let error = |message: String| Args::command().error(ErrorKind::InvalidValue, message);
+    let mut command = Args::command();
+    let options: Vec<(String, String)> = command
+        .get_arguments()
+        .filter_map(|arg| Some((arg.get_id().to_string(), arg.get_long()?.to_string())))
+        .filter(|(_, long)| long != "config")
+        .collect();
+
+    let (path, explicit) = config_path(&argv, env);
+    let mut from_file = HashSet::new();
+    let mut bindings = Vec::new();
+    let content = match &path {
+        Some(path) if explicit || path.is_file() => Some(
+            fs::read_to_string(path)
+                .map_err(|err| error(format!("{}: {}", path.display(), err)))?,
+        ),
+        _ => None,
+    };
+    if let (Some(path), Some(content)) = (&path, content) {
+        let path = path.display();
+        let entries = parse(&content).map_err(|err| error(format!("{}:{}", path, err)))?;
+        for entry in entries {
+            let error = |message: String| error(format!("{}:{}: {}", path, entry.line, message));
+            if entry.table == BINDINGS {
+                let [action] = &entry.values[..] else {
+                    return Err(error("a key takes a single action".to_string()));
+                };
+                keys::parse_binding(&entry.key, action).map_err(error)?;
+                bindings.push((entry.key, action.clone()));
+                continue;
+            }
+            let (id, _) = options
+                .iter()
+                .find(|(_, long)| *long == entry.key)
+                .ok_or_else(|| error(format!("unknown option \"{}\"", entry.key)))?;
+            let table = table(&entry.key);
+            if table != entry.table {
+                return Err(error(match table {
+                    "" => format!("\"{}\" goes at the top, before any table", entry.key),
+                    table => format!("\"{}\" goes in the [{}] table", entry.key, table),
+                }));
+            }
+
+            let arg = argument(&command, id);
+            if entry.values.len() != 1 && !matches!(arg.get_action(), ArgAction::Append) {
+                return Err(error(format!("\"{}\" takes a single value", entry.key)));
+            }
+            for value in &entry.values {
+                check_value(arg, value).map_err(error)?;
+            }
+            let values = entry.values;
+            command = command.mut_arg(id, |arg| arg.default_values(values));
+            from_file.insert(id.clone());
+        }
+    }
+
+    // The environment goes over the file the same way, as defaults the command line overrides
+    let mut from_env = HashMap::new();
+    for (id, long) in &options {
+        let name = env_name(long);
+        let Some(value) = env(&name).filter(|value| !value.is_empty()) else {
+            continue;
+        };
+        let error = |message: String| error(format!("{}: {}", name, message));
+        let value = value
+            .into_string()
+            .map_err(|_| error("not valid UTF-8".to_string()))?;
+        let arg = argument(&command, id);
+        // Flags take 1 and 0 as well as true and false from the environment
+        let value = match arg.get_action() {
+            ArgAction::SetTrue => env_flag(&value)
+                .ok_or_else(|| {
+                    error(format!(
+                        "\"{}\" is not true or false, 1 or 0, yes or no, on or off",
+                        value
+                    ))
+                })?
+                .to_string(),
+            _ => value,
+        };
+        check_value(arg, &value).map_err(error)?;
+        command = command.mut_arg(id, |arg| arg.default_value(value));
+        from_file.remove(id);
+        from_env.insert(id.clone(), name);
+    }
+
+    let matches = command.try_get_matches_from(argv)?;
+    let args = Args::from_arg_matches(&matches)?;
+    Ok(Config {
+        args,
+        bindings,
+        matches,
+        path,
+        from_file,
+        from_env,
+    })
+}
+
+fn argument<'a>(command: &'a clap::Command, id: &str) -> &'a Arg {
+    command
+        .get_arguments()
+        .find(|arg| arg.get_id() == id)
+        .unwrap()
+}
+
+// A flag given as any of the words clap takes for true or false
+fn env_flag(value: &str) -> Option<&'static str> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "y" | "on" => Some("true"),
+        "false" | "0" | "no" | "n" | "off" => Some("false"),
+        _ => None,
+    }
+}
+
+// The file named by --config or RS_CUBE_CONFIG, which has to exist, or else config.toml in the
+// rs-cube directory of the XDG config directory
+fn config_path(
+    argv: &[OsString],
+    env: &dyn Fn(&str) -> Option<OsString>,
+) -> (Option<PathBuf>, bool) {
+    let mut argv = argv.iter().skip(1).map(|arg| arg.to_string_lossy());
+    while let Some(arg) = argv.next() {
+        if arg == "--" {
+            break;
+        } else if arg == "--config" {
+            return (argv.next().map(|path| PathBuf::from(path.as_ref())), true);
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            return (Some(PathBuf::from(path)), true);
+        }
+    }
+    if let Some(path) = env(&format!("{}CONFIG", ENV_PREFIX)) {
+        return (Some(PathBuf::from(path)), true);
+    }
+
+    // Relative paths in XDG_CONFIG_HOME are to be ignored
+    let dir = env("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env("HOME").map(|home| PathBuf::from(home).join(".config")));
+    (
+        dir.map(|dir| dir.join("rs-cube").join("config.toml")),
+        false,
+    )
+}
+
+// Parses the value as it would be given on the command line, apart from flags which take true
+// or false
+fn check_value(arg: &Arg, value: &str) -> Result<(), String> {
+    if matches!(arg.get_action(), ArgAction::SetTrue) {
+        return match value {
+            "true" | "false" => Ok(()),
+            _ => Err(format!(
+                "\"{}\" must be true or false",
+                arg.get_long().unwrap()
+            )),
+        };
+    }
+    clap::Command::new("rs-cube")
+        .no_binary_name(true)
+        .arg(arg.clone())
+        .try_get_matches_from([format!("--{}={}", arg.get_long().unwrap(), value)])
+        .map(|_| ())
+        .map_err(|err| clap_message(&err))
+}
+
+fn env_name(long: &str) -> String {
+    format!("{}{}", ENV_PREFIX, long.replace('-', "_").to_uppercase())
+}
+
+fn table(long: &str) -> &'static str {
+    TABLES
+        .iter()
+        .find(|(_, options)| options.contains(&long))
+        .map_or("", |(table, _)| table)
+}
+
+// The message of a clap error without its "error: " prefix and usage
+fn clap_message(err: &clap::Error) -> String {
+    let message = err.to_string();
+    let message = message.lines().next().unwrap_or_default();
+    message
+        .strip_prefix("error: ")
+        .unwrap_or(message)
+        .to_string()
+}
+
+// Reads the part of TOML the options need: `[table]` headers and `key = value` lines where the
+// value is a string, a number, true or false, or an array of those on one line
+fn parse(content: &str) -> Result<Vec<Entry>, String> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut table = String::new();
+    for (number, line) in content.lines().enumerate() {
+        let error = |message: String| format!("{}: {}", number + 1, message);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let (name, rest) = header
+                .split_once(']')
+                .ok_or_else(|| error("missing closing bracket".to_string()))?;
+            expect_end(rest).map_err(error)?;
+            table = name.trim().to_string();
+            if table != BINDINGS && !TABLES.iter().any(|(known, _)| *known == table) {
+                return Err(error(format!("unknown table [{}]", table)));
+            }
+            continue;
+        }
+
+        // Keys such as "=" or "[" need quotes
+        let (key, value) = match line.starts_with(['"', '\'']) {
+            true => parse_scalar(line)
+                .map_err(error)?
+                .1
+                .trim_start()
+                .strip_prefix('=')
+                .map(|value| (parse_scalar(line).unwrap().0, value)),
+            false => line
+                .split_once('=')
+                .map(|(key, value)| (key.trim().to_string(), value)),
+        }
+        .ok_or_else(|| error("expected `key = value`".to_string()))?;
+        if entries
+            .iter()
+            .any(|entry| entry.table == table && entry.key == key)
+        {
+            return Err(error(format!("\"{}\" is set twice", key)));
+        }
+        let values = parse_value(value.trim()).map_err(error)?;
+        entries.push(Entry {
+            table: table.clone(),
+            key,
+            values,
+            line: number + 1,
+        });
+    }
+    Ok(entries)
+}
+
+// An array becomes one value per element, anything else a single value
+fn parse_value(value: &str) -> Result<Vec<String>, String> {
+    let Some(mut rest) = value.strip_prefix('[') else {
+        let (value, rest) = parse_scalar(value)?;
+        expect_end(rest)?;
+        return Ok(vec![value]);
+    };
+
+    let mut values = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix(']') {
+            expect_end(after)?;
+            return Ok(values);
+        }
+        let (value, after) = parse_scalar(rest)?;
+        values.push(value);
+        rest = after.trim_start();
+        match rest.strip_prefix(',') {
+            Some(after) => rest = after,
+            None if rest.starts_with(']') => {}
+            None => return Err("expected `,` or `]` in array".to_string()),
+        }
+    }
+}
+
+// A quoted string, true or false or a number at the start of `s`, and what follows it
+fn parse_scalar(s: &str) -> Result<(String, &str), String> {
+    if let Some(literal) = s.strip_prefix('\'') {
+        let (value, rest) = literal
+            .split_once('\'')
+            .ok_or_else(|| "missing closing quote".to_string())?;
+        return Ok((value.to_string(), rest));
+    }
+    if let Some(quoted) = s.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => return Ok((value, &quoted[index + 1..])),
+                '\\' => value.push(match chars.next().map(|(_, c)| c) {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    _ => return Err("unknown escape in string".to_string()),
+                }),
+                c => value.push(c),
+            }
+        }
+        return Err("missing closing quote".to_string());
+    }
+
+    let end = s
+        .find(|c: char| c == ',' || c == ']' || c == '#' || c.is_whitespace())
+        .unwrap_or(s.len());
+    let (word, rest) = s.split_at(end);
+    let number = word.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+')
+        && word.replace('_', "").parse::<f64>().is_ok();
+    match word {
+        "true" | "false" => Ok((word.to_string(), rest)),
+        _ if number => Ok((word.replace('_', ""), rest)),
+        "" => Err("missing value".to_string()),
+        _ => Err(format!("\"{}\" needs quotes to be a string", word)),
+    }
+}
+
+fn expect_end(rest: &str) -> Result<(), String> {
+    let rest = rest.trim();
+    match rest.is_empty() || rest.starts_with('#') {
+        true => Ok(()),
+        false => Err(format!("unexpected \"{}\"", rest)),
+    }
+}
+
+pub fn run(args: &ConfigArgs, config: &Config) -> Result<(), String> {
+    match args.action {
+        ConfigAction::Dump => {
+            print!("{}", dump(config));
+            Ok(())
+        }
+    }
+}
+
+// The options in effect as a config file, with the source of every value that isn't a default
+fn dump(config: &Config) -> String {
+    let mut out = match &config.path {
+        Some(path) if path.is_file() => format!("# Read from {}\n", path.display()),
+        Some(path) => format!("# No config file at {}\n", path.display()),
+        None => "# No config file\n".to_string(),
+    };
+
+    let command = Args::command();
+    let arguments: Vec<&Arg> = command
+        .get_arguments()
+        .filter(|arg| arg.get_long().is_some_and(|long| long != "config"))
+        .collect();
+    for name in std::iter::once("").chain(TABLES.iter().map(|(table, _)| *table)) {
+        if !name.is_empty() {
+            out.push_str(&format!("\n[{}]\n", name));
+        }
+        for arg in arguments
+            .iter()
+            .filter(|arg| table(arg.get_long().unwrap()) == name)
+        {
+            let id = arg.get_id().as_str();
+            let Some(raw) = config.matches.get_raw(id) else {
+                continue;
+            };
+            let values: Vec<String> = raw.map(|v| toml_value(&v.to_string_lossy())).collect();
+            let value = match arg.get_action() {
+                ArgAction::Append => format!("[{}]", values.join(", ")),
+                // The environment can give a flag as 1 or 0, which the file takes as true or false
+                ArgAction::SetTrue => config.matches.get_flag(id).to_string(),
+                _ => values.join(", "),
+            };
+            let long = arg.get_long().unwrap();
+            let source = match config.matches.value_source(id) {
+                Some(ValueSource::CommandLine) => " # command line".to_string(),
+                _ if config.from_env.contains_key(id) => format!(" # {}", config.from_env[id]),
+                _ if config.from_file.contains(id) => " # config file".to_string(),
+                _ => String::new(),
+            };
+            out.push_str(&format!("{} = {}{}\n", long, value, source));
+        }
+    }
+    if !config.bindings.is_empty() {
+        out.push_str(&format!("\n[{}]\n", BINDINGS));
+        for (key, action) in &config.bindings {
+            out.push_str(&format!(
+                "{} = {} # config file\n",
+                toml_value(key),
+                toml_value(action)
+            ));
+        }
+    }
+    out
+}
+
+// Numbers and booleans as they are, anything else as a quoted string
+fn toml_value(value: &str) -> String {
+    let number =
+        value.starts_with(|c: char| c.is_ascii_digit() || c == '-') && value.parse::<f64>().is_ok();
+    if number || value == "true" || value == "false" {
+        return value.to_string();
+    }
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_with(name: &str, file: &str, args: &[&str]) -> Result<Config, String> {
+        load_with_env(name, file, args, &[])
+    }
+
+    // Loads the options with `vars` as the whole environment
+    fn load_with_env(
+        name: &str,
+        file: &str,
+        args: &[&str],
+        vars: &[(&str, &str)],
+    ) -> Result<Config, String> {
+        let path =
+            std::env::temp_dir().join(format!("rs-cube-{}-{}.toml", name, std::process::id()));
+        fs::write(&path, file).unwrap();
+        let mut argv = vec![
+            "rs-cube".into(),
+            "--config".into(),
+            path.clone().into_os_string(),
+        ];
+        argv.extend(args.iter().map(Into::into));
+        let env = |name: &str| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.into())
+        };
+        let config = load_from(argv, &env);
+        fs::remove_file(&path).unwrap();
+        config.map_err(|err| clap_message(&err).replace(&path.display().to_string(), "config.toml"))
+    }
+
+    #[test]
+    fn command_line_over_environment_over_file_over_defaults() {
+        let file = "fps-limit = 10\n\n[camera]\nfov = 30\ndistance = 20\n";
+        let vars = [("RS_CUBE_FOV", "40"), ("RS_CUBE_DISTANCE", "25")];
+        let config = load_with_env("precedence", file, &["--distance", "30"], &vars).unwrap();
+
+        assert_eq!(config.args.fps_limit, 10);
+        assert_eq!(config.args.camera.fov, 40.0);
+        assert_eq!(config.args.camera.distance, 30.0);
+        assert_eq!(config.args.move_interval, 500);
+
+        let dump = dump(&config);
+        assert!(dump.contains("fps-limit = 10 # config file\n"));
+        assert!(dump.contains("fov = 40 # RS_CUBE_FOV\n"));
+        assert!(dump.contains("distance = 30 # command line\n"));
+        assert!(dump.contains("move-interval = 500\n"));
+    }
+
+    #[test]
+    fn flags_from_environment() {
+        let vars = [("RS_CUBE_EDGES", "1"), ("RS_CUBE_PER_PIXEL", "0")];
+        let file = "[lighting]\nper-pixel = true\n";
+        let config = load_with_env("flags", file, &[], &vars).unwrap();
+
+        assert!(config.args.edges);
+        assert!(!config.args.lighting.per_pixel);
+        let dump = dump(&config);
+        assert!(dump.contains("edges = true # RS_CUBE_EDGES\n"));
+        assert!(dump.contains("per-pixel = false # RS_CUBE_PER_PIXEL\n"));
+    }
+
+    #[test]
+    fn environment_errors() {
+        let cases = [
+            (
+                ("RS_CUBE_EDGES", "maybe"),
+                "RS_CUBE_EDGES: \"maybe\" is not true or false",
+            ),
+            (
+                ("RS_CUBE_FOV", "500"),
+                "RS_CUBE_FOV: invalid value '500' for '--fov <FOV>'",
+            ),
+        ];
+        for (var, message) in cases {
+            let err = load_with_env("env-errors", "", &[], &[var]).err().unwrap();
+            assert!(err.starts_with(message), "{:?} gave {:?}", var, err);
+        }
+    }
+
+    #[test]
+    fn file_values() {
+        let file = "# comment\nstate = 'UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB'\n\
+                    moves = \"R U R' U'\" # played\n\n\
+                    [lighting]\nlight = [\"dir:0,1,-1\", 'point:1,2,-3:ff0000']\n\n\
+                    [bindings]\n\"=\" = \"reset-cube\"\nspace = \"quit\"\n";
+        let config = load_with("values", file, &[]).unwrap();
+        assert_eq!(config.args.moves.as_deref(), Some("R U R' U'"));
+        assert_eq!(config.args.lighting.lights.len(), 2);
+        assert_eq!(
+            config.bindings,
+            [
+                ("=".to_string(), "reset-cube".to_string()),
+                ("space".to_string(), "quit".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let cases = [
+            ("[camera\n", "1: missing closing bracket"),
+            ("[timer]\n", "1: unknown table [timer]"),
+            ("\nfov\n", "2: expected `key = value`"),
+            ("fov = 1\nfov = 2\n", "2: \"fov\" is set twice"),
+            (
+                "theme = classic\n",
+                "1: \"classic\" needs quotes to be a string",
+            ),
+            ("theme = \"classic\n", "1: missing closing quote"),
+            ("theme = \"a\\qb\"\n", "1: unknown escape in string"),
+            ("light = [\"a\" \"b\"]\n", "1: expected `,` or `]` in array"),
+            ("fov = 1 2\n", "1: unexpected \"2\""),
+            ("fov =\n", "1: missing value"),
+        ];
+        for (content, message) in cases {
+            assert_eq!(
+                parse(content).err().as_deref(),
+                Some(message),
+                "{:?}",
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn option_errors() {
+        let cases = [
+            ("colour = 1\n", "config.toml:1: unknown option \"colour\""),
+            (
+                "fov = 30\n",
+                "config.toml:1: \"fov\" goes in the [camera] table",
+            ),
+            (
+                "[camera]\ntheme = \"classic\"\n",
+                "config.toml:2: \"theme\" goes at the top, before any table",
+            ),
+            (
+                "edges = 1\n",
+                "config.toml:1: \"edges\" must be true or false",
+            ),
+            (
+                "fps-limit = [1, 2]\n",
+                "config.toml:1: \"fps-limit\" takes a single value",
+            ),
+            (
+                "[camera]\nprojection = \"fisheye\"\n",
+                "config.toml:2: invalid value 'fisheye' for '--projection <PROJECTION>'",
+            ),
+            (
+                "[camera]\nfov = 500\n",
+                "config.toml:2: invalid value '500' for '--fov <FOV>': 500 is not in 10..=120",
+            ),
+            (
+                "[bindings]\nx = \"spin\"\n",
+                "config.toml:2: unknown action \"spin\"",
+            ),
+        ];
+        for (content, message) in cases {
+            let err = load_with("errors", content, &[]).err().unwrap();
+            assert!(err.starts_with(message), "{:?} gave {:?}", content, err);
+        }
+    }
+}
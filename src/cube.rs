@@ -0,0 +1,303 @@
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Face {
+    U,
+    R,
+    F,
+    D,
+    L,
+    B,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [Face::U, Face::R, Face::F, Face::D, Face::L, Face::B];
+
+    pub fn from_char(c: char) -> Option<Face> {
+        match c {
+            'U' => Some(Face::U),
+            'R' => Some(Face::R),
+            'F' => Some(Face::F),
+            'D' => Some(Face::D),
+            'L' => Some(Face::L),
+            'B' => Some(Face::B),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Face::U => 'U',
+            Face::R => 'R',
+            Face::F => 'F',
+            Face::D => 'D',
+            Face::L => 'L',
+            Face::B => 'B',
+        }
+    }
+
+    /// Outward normal, right and down vectors of the face when it is unfolded into the
+    /// standard net, in cube coordinates (X right, Y up, Z towards the viewer)
+    fn frame(self) -> ([i8; 3], [i8; 3], [i8; 3]) {
+        match self {
+            Face::U => ([0, 1, 0], [1, 0, 0], [0, 0, 1]),
+            Face::R => ([1, 0, 0], [0, 0, -1], [0, -1, 0]),
+            Face::F => ([0, 0, 1], [1, 0, 0], [0, -1, 0]),
+            Face::D => ([0, -1, 0], [1, 0, 0], [0, 0, -1]),
+            Face::L => ([-1, 0, 0], [0, 0, 1], [0, -1, 0]),
+            Face::B => ([0, 0, -1], [-1, 0, 0], [0, -1, 0]),
+        }
+    }
+}
+
+/// Cubie position and outward normal of a facelet, with positions in {-1, 0, 1}
+pub fn facelet_geometry(index: usize) -> ([i8; 3], [i8; 3]) {
+    let (normal, right, down) = Face::ALL[index / 9].frame();
+    let row = (index % 9 / 3) as i8 - 1;
+    let col = (index % 3) as i8 - 1;
+
+    let mut pos = [0i8; 3];
+    for axis in 0..3 {
+        pos[axis] = normal[axis] + col * right[axis] + row * down[axis];
+    }
+    (pos, normal)
+}
+
+/// Facelet index of the sticker on the cubie at `pos` facing along `normal`
+pub fn facelet_from_geometry(pos: [i8; 3], normal: [i8; 3]) -> usize {
+    (0..54)
+        .find(|&index| facelet_geometry(index) == (pos, normal))
+        .expect("facelet geometry is closed under rotation")
+}
+
+/// Corners of a facelet on the unit cube in cube coordinates, clockwise from the top left
+/// as seen from outside the cube
+pub fn facelet_corners(index: usize) -> [[f32; 3]; 4] {
+    let (pos, normal) = facelet_geometry(index);
+    let (_, right, down) = Face::ALL[index / 9].frame();
+
+    let mut center = [0.0f32; 3];
+    for axis in 0..3 {
+        center[axis] = if normal[axis] != 0 {
+            normal[axis] as f32 * 0.5
+        } else {
+            pos[axis] as f32 / 3.0
+        };
+    }
+
+    [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(r, d): (f32, f32)| {
+        let mut corner = center;
+        for axis in 0..3 {
+            corner[axis] += (r * right[axis] as f32 + d * down[axis] as f32) / 6.0;
+        }
+        corner
+    })
+}
+
+/// Facelet index of a point on the surface of the unit cube given in cube coordinates
+pub fn facelet_at(face: Face, pos: [f32; 3]) -> u8 {
+    let (_, right, down) = face.frame();
+    let project = |dir: [i8; 3]| -> usize {
+        let d: f32 = (0..3).map(|axis| pos[axis] * dir[axis] as f32).sum();
+        (((d + 0.5) * 3.0).floor() as i32).clamp(0, 2) as usize
+    };
+
+    (face as usize * 9 + project(down) * 3 + project(right)) as u8
+}
+
+/// Rotate a vector by a number of quarter turns counter-clockwise around a positive axis
+fn rotate(v: [i8; 3], axis: usize, quarter_turns: i8) -> [i8; 3] {
+    let mut v = v;
+    for _ in 0..quarter_turns.rem_euclid(4) {
+        v = match axis {
+            0 => [v[0], -v[2], v[1]],
+            1 => [v[2], v[1], -v[0]],
+            _ => [-v[1], v[0], v[2]],
+        };
+    }
+    v
+}
+
+/// Move letters in standard notation: faces, wide faces, slices and whole cube rotations
+const MOVE_LETTERS: &str = "URFDLBurfdlbMESxyz";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Move {
+    letter: char,
+    amount: i8,
+}
+
+impl Move {
+    /// Move of a face (`URFDLB`), a wide face (`urfdlb`), a slice (`MES`) or the whole cube
+    /// (`xyz`) by `amount` clockwise quarter turns: 1, 2 or -1
+    pub fn new(letter: char, amount: i8) -> Result<Move, String> {
+        if !MOVE_LETTERS.contains(letter) {
+            return Err(format!("invalid move letter '{}'", letter));
+        }
+        if ![1, 2, -1].contains(&amount) {
+            return Err(format!(
+                "invalid move amount {}, expected 1, 2 or -1",
+                amount
+            ));
+        }
+        Ok(Move { letter, amount })
+    }
+
+    /// Move letter in standard notation
+    pub fn letter(&self) -> char {
+        self.letter
+    }
+
+    /// Clockwise quarter turns: 1, 2 or -1
+    pub fn amount(&self) -> i8 {
+        self.amount
+    }
+
+    /// Axis, layer mask (bit 0 = -1, bit 1 = 0, bit 2 = +1) and counter-clockwise
+    /// quarter turns around the positive axis
+    pub fn spec(&self) -> (usize, u8, i8) {
+        let (axis, layers, direction) = match self.letter {
+            'R' => (0, 0b100, -1),
+            'L' => (0, 0b001, 1),
+            'U' => (1, 0b100, -1),
+            'D' => (1, 0b001, 1),
+            'F' => (2, 0b100, -1),
+            'B' => (2, 0b001, 1),
+            'r' => (0, 0b110, -1),
+            'l' => (0, 0b011, 1),
+            'u' => (1, 0b110, -1),
+            'd' => (1, 0b011, 1),
+            'f' => (2, 0b110, -1),
+            'b' => (2, 0b011, 1),
+            'M' => (0, 0b010, 1),
+            'E' => (1, 0b010, 1),
+            'S' => (2, 0b010, -1),
+            'x' => (0, 0b111, -1),
+            'y' => (1, 0b111, -1),
+            'z' => (2, 0b111, -1),
+            _ => unreachable!("move letters are checked by Move::new"),
+        };
+        (axis, layers, direction * self.amount)
+    }
+
+    pub fn inverse(&self) -> Move {
+        Move {
+            letter: self.letter,
+            amount: if self.amount == 2 { 2 } else { -self.amount },
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let suffix = match self.amount {
+            2 => "2",
+            -1 => "'",
+            _ => "",
+        };
+        write!(f, "{}{}", self.letter, suffix)
+    }
+}
+
+/// Parse a move sequence in standard notation, e.g. `R U R' U2 Fw x'`
+pub fn parse_alg(alg: &str) -> Result<Vec<Move>, String> {
+    let mut moves = Vec::new();
+    let mut chars = alg.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() || c == '(' || c == ')' {
+            continue;
+        }
+        if !MOVE_LETTERS.contains(c) {
+            return Err(format!("invalid move '{}' in \"{}\"", c, alg));
+        }
+
+        let mut letter = c;
+        if chars.peek() == Some(&'w') && "URFDLB".contains(c) {
+            chars.next();
+            letter = c.to_ascii_lowercase();
+        }
+
+        let mut amount: i8 = 1;
+        if chars.peek() == Some(&'2') {
+            chars.next();
+            amount = 2;
+        }
+        if chars.peek() == Some(&'\'') {
+            chars.next();
+            if amount == 1 {
+                amount = -1;
+            }
+        }
+
+        moves.push(Move { letter, amount });
+    }
+
+    Ok(moves)
+}
+
+pub fn invert_alg(moves: &[Move]) -> Vec<Move> {
+    moves.iter().rev().map(Move::inverse).collect()
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Cube {
+    /// Facelets in URFDLB face order, each face row by row as laid out in the unfolded net
+    pub facelets: [Face; 54],
+}
+
+impl Cube {
+    pub fn solved() -> Cube {
+        let mut facelets = [Face::U; 54];
+        for (index, facelet) in facelets.iter_mut().enumerate() {
+            *facelet = Face::ALL[index / 9];
+        }
+        Cube { facelets }
+    }
+
+    /// Parse a 54 character facelet string such as `UUUUUUUUURRRRRRRRRFFF...`
+    pub fn from_facelets(state: &str) -> Result<Cube, String> {
+        let faces: Vec<Face> = state
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| Face::from_char(c).ok_or(format!("invalid facelet '{}'", c)))
+            .collect::<Result<_, _>>()?;
+
+        let facelets: [Face; 54] = faces
+            .try_into()
+            .map_err(|faces: Vec<Face>| format!("expected 54 facelets, got {}", faces.len()))?;
+        Ok(Cube { facelets })
+    }
+
+    pub fn apply(&mut self, m: &Move) {
+        let (axis, layers, quarter_turns) = m.spec();
+        let prev = self.facelets;
+
+        for (index, &facelet) in prev.iter().enumerate() {
+            let (pos, normal) = facelet_geometry(index);
+            if layers & (1 << (pos[axis] + 1)) == 0 {
+                continue;
+            }
+            let target = facelet_from_geometry(
+                rotate(pos, axis, quarter_turns),
+                rotate(normal, axis, quarter_turns),
+            );
+            self.facelets[target] = facelet;
+        }
+    }
+
+    pub fn apply_alg(&mut self, moves: &[Move]) {
+        for m in moves {
+            self.apply(m);
+        }
+    }
+}
+
+impl fmt::Display for Cube {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for facelet in self.facelets {
+            write!(f, "{}", facelet.to_char())?;
+        }
+        Ok(())
+    }
+}
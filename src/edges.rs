@@ -0,0 +1,90 @@
+use crate::supersample::Samples;
+
+// Replaces the characters of cells on the outline of the cube or of a sticker with a glyph
+// following the edge. Each edge is drawn on one side only: the cube's side of its silhouette,
+// the sticker's side where a sticker meets the body and the nearer side where two stickers
+// meet. The edge direction comes from a Sobel filter over the cells of the same colour
+pub fn outline(buffer: &mut [char], cbuffer: &[&str], samples: &Samples, aspect: f32, body: &str) {
+    let factor = samples.factor as usize;
+    let width = samples.width as usize / factor;
+    let height = buffer.len() / width;
+
+    // Nearest sample of each cell
+    let mut depth = vec![0.0f32; buffer.len()];
+    for (index, sample) in samples.zbuffer.iter().enumerate() {
+        let (x, y) = (
+            index % samples.width as usize,
+            index / samples.width as usize,
+        );
+        let cell = &mut depth[y / factor * width + x / factor];
+        *cell = cell.max(*sample);
+    }
+
+    let mut glyphs: Vec<(usize, char)> = Vec::new();
+    for index in 0..buffer.len() {
+        if buffer[index] == ' ' {
+            continue;
+        }
+        let (x, y) = ((index % width) as isize, (index / width) as isize);
+        let neighbour = |dx: isize, dy: isize| -> Option<usize> {
+            let (nx, ny) = (x + dx, y + dy);
+            let inside = nx >= 0 && ny >= 0 && nx < width as isize && ny < height as isize;
+            inside.then(|| ny as usize * width + nx as usize)
+        };
+        let same = |dx: isize, dy: isize| -> f32 {
+            let same =
+                neighbour(dx, dy).is_some_and(|n| buffer[n] != ' ' && cbuffer[n] == cbuffer[index]);
+            same as u8 as f32
+        };
+
+        let on_edge =
+            [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .iter()
+                .any(|&(dx, dy)| match neighbour(dx, dy) {
+                    None => true,
+                    Some(n) if buffer[n] == ' ' => true,
+                    Some(n) if cbuffer[n] == cbuffer[index] => false,
+                    Some(n) => {
+                        cbuffer[index] != body && (cbuffer[n] == body || depth[index] > depth[n])
+                    }
+                });
+        if !on_edge {
+            continue;
+        }
+
+        // Gradient pointing into the region, with rows `aspect` times as far apart as columns
+        let gx = same(1, -1) + 2.0 * same(1, 0) + same(1, 1)
+            - same(-1, -1)
+            - 2.0 * same(-1, 0)
+            - same(-1, 1);
+        let gy = same(-1, 1) + 2.0 * same(0, 1) + same(1, 1)
+            - same(-1, -1)
+            - 2.0 * same(0, -1)
+            - same(1, -1);
+        let gy = gy / aspect;
+        if gx == 0.0 && gy == 0.0 {
+            continue;
+        }
+
+        let angle = gy.atan2(gx).to_degrees().rem_euclid(180.0);
+        let glyph = if !(22.5..157.5).contains(&angle) {
+            '|'
+        } else if angle < 67.5 {
+            '/'
+        } else if angle < 112.5 {
+            // Underscores hug the bottom of the cell, so they only suit bottom edges
+            if gy < 0.0 {
+                '_'
+            } else {
+                '-'
+            }
+        } else {
+            '\\'
+        };
+        glyphs.push((index, glyph));
+    }
+
+    for (index, glyph) in glyphs {
+        buffer[index] = glyph;
+    }
+}
@@ -0,0 +1,92 @@
+use crate::ansi_escape_code::{CursorForward, SetCursorPos};
+use crate::renderer::Frame;
+use std::io::{self, Write};
+
+// Number of decimal digits of a positive number
+fn digits(n: usize) -> usize {
+    n.checked_ilog10().unwrap_or(0) as usize + 1
+}
+
+// Bytes of "ESC [ row ; col H" for the zero-based cell (x, y)
+fn position_len(x: usize, y: usize) -> usize {
+    4 + digits(y + 1) + digits(x + 1)
+}
+
+// Bytes of "ESC [ n C"
+fn forward_len(n: usize) -> usize {
+    match n {
+        1 => 3,
+        n => 3 + digits(n),
+    }
+}
+
+// Bytes of CR, `lines` LFs and a move right to column x
+fn next_line_len(x: usize, lines: usize) -> usize {
+    1 + lines
+        + match x {
+            0 => 0,
+            x => forward_len(x),
+        }
+}
+
+// Writes the cells that changed since the previous frame with as few bytes as it can:
+//
+// - the cursor is tracked, so neighbouring changes need no move at all
+// - gaps of unchanged cells on the same line are skipped with a relative move, or written
+//   again when that is shorter and they are in the colour already set
+// - later lines are reached with CR and LF when that beats an absolute position
+// - the colour is tracked across the frame and only set when it changes
+//
+// Nothing is assumed about the cursor or colour left behind by earlier writes
+pub fn write_changes(frame: &Frame, mut handle: impl Write, use_color: bool) -> io::Result<()> {
+    let width = frame.width as usize;
+    let chars = &frame.chars[..frame.chars.len().min(frame.prev_chars.len())];
+    // Position after the last written character, unknown until the first absolute move and
+    // after writing the last column, where terminals differ in where they leave it
+    let mut cursor: Option<(usize, usize)> = None;
+    let mut sgr: Option<&str> = None;
+
+    for (index, &val) in chars.iter().enumerate() {
+        let color = frame.colors[index];
+        if val == frame.prev_chars[index] && color == frame.prev_colors[index] {
+            continue;
+        }
+        let (x, y) = (index % width, index / width);
+
+        match cursor {
+            Some((cx, cy)) if cy == y && cx < x => {
+                let start = index - (x - cx);
+                // Spaces only show the background, which no cell colour changes
+                let reusable = (start..index)
+                    .all(|i| !use_color || chars[i] == ' ' || Some(frame.colors[i]) == sgr);
+                let gap_len: usize = chars[start..index].iter().map(|c| c.len_utf8()).sum();
+                if reusable && gap_len <= forward_len(x - cx) {
+                    for &c in &chars[start..index] {
+                        write!(handle, "{}", c)?;
+                    }
+                } else {
+                    write!(handle, "{}", CursorForward((x - cx) as u16))?;
+                }
+            }
+            Some((cx, cy)) if cy == y && cx == x => {}
+            Some((_, cy)) if cy < y && next_line_len(x, y - cy) < position_len(x, y) => {
+                handle.write_all(b"\r")?;
+                for _ in cy..y {
+                    handle.write_all(b"\n")?;
+                }
+                if x > 0 {
+                    write!(handle, "{}", CursorForward(x as u16))?;
+                }
+            }
+            _ => write!(handle, "{}", SetCursorPos(y as u16 + 1, x as u16 + 1))?,
+        }
+
+        if use_color && sgr != Some(color) && !(val == ' ' && sgr.is_some()) {
+            write!(handle, "{}", color)?;
+            sgr = Some(color);
+        }
+        write!(handle, "{}", val)?;
+        cursor = (x + 1 < width).then_some((x + 1, y));
+    }
+    Ok(())
+}
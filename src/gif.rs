@@ -0,0 +1,300 @@
+use std::io::{self, Write};
+
+const MAX_CODE: u16 = 4096;
+
+pub struct Encoder<W: Write> {
+    w: W,
+    width: u16,
+    height: u16,
+    min_code_size: u8,
+}
+
+impl<W: Write> Encoder<W> {
+    // Writes the header, global colour table and an infinite loop extension
+    pub fn new(mut w: W, width: u16, height: u16, palette: &[[u8; 3]]) -> io::Result<Self> {
+        assert!(!palette.is_empty() && palette.len() <= 256);
+
+        let table_bits = (palette.len().next_power_of_two().trailing_zeros() as u8).max(1);
+
+        w.write_all(b"GIF89a")?;
+        w.write_all(&width.to_le_bytes())?;
+        w.write_all(&height.to_le_bytes())?;
+        w.write_all(&[0x80 | ((table_bits - 1) << 4) | (table_bits - 1), 0, 0])?;
+        for index in 0..(1usize << table_bits) {
+            w.write_all(palette.get(index).unwrap_or(&[0, 0, 0]))?;
+        }
+
+        // NETSCAPE2.0 application extension, loop count 0 = forever
+        w.write_all(&[0x21, 0xFF, 0x0B])?;
+        w.write_all(b"NETSCAPE2.0")?;
+        w.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+        Ok(Encoder {
+            w,
+            width,
+            height,
+            min_code_size: table_bits.max(2),
+        })
+    }
+
+    // `indices` holds one palette index per pixel, `delay` is in hundredths of a second
+    pub fn write_frame(&mut self, indices: &[u8], delay: u16) -> io::Result<()> {
+        assert!(indices.len() == self.width as usize * self.height as usize);
+
+        // Graphic control extension
+        self.w.write_all(&[0x21, 0xF9, 0x04, 0x00])?;
+        self.w.write_all(&delay.to_le_bytes())?;
+        self.w.write_all(&[0x00, 0x00])?;
+
+        // Image descriptor covering the whole canvas
+        self.w.write_all(&[0x2C, 0, 0, 0, 0])?;
+        self.w.write_all(&self.width.to_le_bytes())?;
+        self.w.write_all(&self.height.to_le_bytes())?;
+        self.w.write_all(&[0x00, self.min_code_size])?;
+
+        for block in lzw_compress(indices, self.min_code_size).chunks(255) {
+            self.w.write_all(&[block.len() as u8])?;
+            self.w.write_all(block)?;
+        }
+        self.w.write_all(&[0x00])
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.w.write_all(&[0x3B])?;
+        Ok(self.w)
+    }
+}
+
+struct CodeWriter {
+    out: Vec<u8>,
+    acc: u32,
+    nbits: u32,
+}
+
+impl CodeWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.acc |= (code as u32) << self.nbits;
+        self.nbits += size;
+        while self.nbits >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.nbits -= 8;
+        }
+    }
+}
+
+fn lzw_compress(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let alphabet = 1usize << min_code_size;
+    let clear_code = alphabet as u16;
+    let end_code = clear_code + 1;
+
+    // next[code * alphabet + index] is the code for `code` extended by `index`, 0 if unset
+    let mut next: Vec<u16> = vec![0; MAX_CODE as usize * alphabet];
+    let mut next_code = end_code + 1;
+    let mut code_size = min_code_size as u32 + 1;
+
+    let mut codes = CodeWriter {
+        out: Vec::new(),
+        acc: 0,
+        nbits: 0,
+    };
+    codes.write(clear_code, code_size);
+
+    let mut iter = indices.iter();
+    let mut prefix = match iter.next() {
+        Some(&index) => index as u16,
+        None => {
+            codes.write(end_code, code_size);
+            codes.write(0, 7);
+            return codes.out;
+        }
+    };
+
+    for &index in iter {
+        let slot = prefix as usize * alphabet + index as usize;
+        if next[slot] != 0 {
+            prefix = next[slot];
+            continue;
+        }
+
+        codes.write(prefix, code_size);
+        if next_code < MAX_CODE {
+            next[slot] = next_code;
+            next_code += 1;
+            if next_code as u32 > (1 << code_size) && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            codes.write(clear_code, code_size);
+            next.fill(0);
+            next_code = end_code + 1;
+            code_size = min_code_size as u32 + 1;
+        }
+        prefix = index as u16;
+    }
+
+    codes.write(prefix, code_size);
+    // The decoder adds one last entry after reading the final code
+    if next_code < MAX_CODE && next_code as u32 + 1 > (1 << code_size) && code_size < 12 {
+        code_size += 1;
+    }
+    codes.write(end_code, code_size);
+    codes.write(0, 7); // Flush the last partial byte
+    codes.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Decodes a GIF LZW stream, returning the indices and the number of clear codes read
+    fn lzw_decompress(data: &[u8], min_code_size: u8) -> (Vec<u8>, usize) {
+        let clear_code = 1u16 << min_code_size;
+        let end_code = clear_code + 1;
+        let reset =
+            || -> Vec<Vec<u8>> { (0..clear_code + 2).map(|code| vec![code as u8]).collect() };
+
+        let mut table = reset();
+        let mut code_size = min_code_size as u32 + 1;
+        let mut prev: Option<u16> = None;
+        let mut out = Vec::new();
+        let mut clears = 0;
+        let (mut acc, mut nbits) = (0u32, 0u32);
+        let mut bytes = data.iter();
+
+        loop {
+            while nbits < code_size {
+                acc |= (*bytes.next().expect("missing end code") as u32) << nbits;
+                nbits += 8;
+            }
+            let code = (acc & ((1 << code_size) - 1)) as u16;
+            acc >>= code_size;
+            nbits -= code_size;
+
+            if code == clear_code {
+                table = reset();
+                code_size = min_code_size as u32 + 1;
+                prev = None;
+                clears += 1;
+                continue;
+            }
+            if code == end_code {
+                return (out, clears);
+            }
+            let entry = match (table.get(code as usize), prev) {
+                (Some(entry), _) => entry.clone(),
+                // The code being defined by this very step
+                (None, Some(prev)) if code as usize == table.len() => {
+                    let mut entry = table[prev as usize].clone();
+                    entry.push(entry[0]);
+                    entry
+                }
+                _ => panic!("code {} is not in the table", code),
+            };
+            out.extend_from_slice(&entry);
+            if let Some(prev) = prev.filter(|_| table.len() < MAX_CODE as usize) {
+                let mut added = table[prev as usize].clone();
+                added.push(entry[0]);
+                table.push(added);
+                if table.len() == 1 << code_size && code_size < 12 {
+                    code_size += 1;
+                }
+            }
+            prev = Some(code);
+        }
+    }
+
+    fn noise(len: usize, alphabet: u32) -> Vec<u8> {
+        let mut state = 12_345u32;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                ((state >> 16) % alphabet) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        for min_code_size in [2, 4, 8] {
+            let alphabet = 1 << min_code_size;
+            let inputs = [
+                Vec::new(),
+                vec![1],
+                vec![0; 5_000],
+                noise(3_000, alphabet),
+                (0..alphabet as u8).cycle().take(20_000).collect(),
+            ];
+            for input in inputs {
+                let (decoded, _) =
+                    lzw_decompress(&lzw_compress(&input, min_code_size), min_code_size);
+                assert_eq!(decoded, input, "min code size {}", min_code_size);
+            }
+        }
+    }
+
+    #[test]
+    fn lzw_clears_full_table() {
+        // Random bytes add a code for almost every byte, filling the 4096 codes several times
+        let input = noise(30_000, 256);
+        let (decoded, clears) = lzw_decompress(&lzw_compress(&input, 8), 8);
+        assert_eq!(decoded, input);
+        assert!(clears > 1, "only the leading clear code was written");
+
+        // Runs of one index need few codes and never fill the table
+        let (_, clears) = lzw_decompress(&lzw_compress(&[3; 30_000], 8), 8);
+        assert_eq!(clears, 1);
+    }
+
+    #[test]
+    fn gif_round_trip() {
+        let palette = [[0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255], [9, 9, 9]];
+        let (width, height) = (23u16, 9u16);
+        let frames = [noise(207, 5), vec![4; 207]];
+
+        let mut encoder = Encoder::new(Vec::new(), width, height, &palette).unwrap();
+        for frame in &frames {
+            encoder.write_frame(frame, 3).unwrap();
+        }
+        let gif = encoder.finish().unwrap();
+
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(gif[6..10], [23, 0, 9, 0]);
+        // Five colours are padded to a table of eight
+        let table_bits = (gif[10] & 0x07) + 1;
+        assert_eq!(table_bits, 3);
+        let table = &gif[13..13 + 3 * 8];
+        assert_eq!(table[..15], palette.concat()[..]);
+
+        let mut rest = &gif[13 + 3 * 8..];
+        let mut decoded = Vec::new();
+        loop {
+            match rest[0] {
+                // Extensions are skipped block by block
+                0x21 => {
+                    rest = &rest[2..];
+                    while rest[0] != 0 {
+                        rest = &rest[1 + rest[0] as usize..];
+                    }
+                    rest = &rest[1..];
+                }
+                0x2C => {
+                    assert_eq!(rest[5..9], [23, 0, 9, 0]);
+                    let min_code_size = rest[10];
+                    rest = &rest[11..];
+                    let mut data = Vec::new();
+                    while rest[0] != 0 {
+                        data.extend_from_slice(&rest[1..1 + rest[0] as usize]);
+                        rest = &rest[1 + rest[0] as usize..];
+                    }
+                    rest = &rest[1..];
+                    decoded.push(lzw_decompress(&data, min_code_size).0);
+                }
+                0x3B => break,
+                byte => panic!("unexpected block {:#04x}", byte),
+            }
+        }
+        assert_eq!(rest, [0x3B]);
+        assert_eq!(decoded, frames);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{font, gif, parse_angles, png};
+use rs_cube::{color, cube, theme, Frame, Renderer, Scene};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::PathBuf;
+
+const BACKGROUND: [u8; 3] = [0x1E, 0x1E, 0x2E];
+// All frames are held in memory as RGB before they are encoded, which this keeps to about 400 MB
+const MAX_PIXELS: u64 = 1 << 27;
+// The renderer keeps a depth, a light level and a colour for each sample
+const MAX_SAMPLES: u64 = 1 << 22;
+
+#[derive(clap::Args)]
+pub struct RenderArgs {
+    /// Output file, `.png` for a still image or `.gif` for an animation
+    #[arg(short, long)]
+    out: PathBuf,
+
+    /// Image width in pixels
+    #[arg(long, default_value_t = 460u16)]
+    width: u16,
+
+    /// Image height in pixels
+    #[arg(long, default_value_t = 348u16)]
+    height: u16,
+
+    /// Number of animation frames, ignored for PNG output
+    #[arg(short = 'n', long, default_value_t = 94u32, value_parser = clap::value_parser!(u32).range(1..))]
+    frames: u32,
+
+    /// Delay between animation frames in milliseconds, at most 655350
+    #[arg(long, default_value_t = 30u32, value_parser = clap::value_parser!(u32).range(..=655_350))]
+    delay: u32,
+
+    /// Rotation angles "a,b,c" of the first frame in radians
+    #[arg(long, value_parser = parse_angles, allow_hyphen_values = true)]
+    angles: Option<(f32, f32, f32)>,
+
+    /// Rotation added to each angle between frames
+    #[arg(long, value_parser = parse_angles, allow_hyphen_values = true, default_value = "0.03,0.02,0.01")]
+    step: (f32, f32, f32),
+
+    /// Glyph scale factor
+    #[arg(long, default_value_t = 1u16, value_parser = clap::value_parser!(u16).range(1..))]
+    scale: u16,
+
+    /// Render shaded pixels instead of rasterised ASCII glyphs
+    #[arg(long, default_value_t = false)]
+    pixels: bool,
+}
+
+enum Format {
+    Png,
+    Gif,
+}
+
+pub fn render(args: &RenderArgs, cube: &cube::Cube, options: &crate::Args) -> Result<(), String> {
+    let theme = &options.theme;
+    let format = match args.out.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("png") => Format::Png,
+        Some(ext) if ext.eq_ignore_ascii_case("gif") => Format::Gif,
+        _ => return Err("output file must end in .png or .gif".to_string()),
+    };
+
+    let (cell_width, cell_height) = if args.pixels {
+        (1, 1)
+    } else {
+        let cell = |glyph: usize| {
+            u16::try_from(glyph + 1)
+                .ok()
+                .and_then(|size| size.checked_mul(args.scale))
+                .ok_or_else(|| format!("--scale {} makes the glyphs too large", args.scale))
+        };
+        (cell(font::GLYPH_WIDTH)?, cell(font::GLYPH_HEIGHT)?)
+    };
+    let (columns, rows) = (args.width / cell_width, args.height / cell_height);
+    if columns == 0 || rows == 0 {
+        return Err("image is too small for a single character cell".to_string());
+    }
+    let aa = u64::from(options.aa);
+    if !Renderer::fits(columns, rows, options.aa)
+        || u64::from(columns) * u64::from(rows) * aa * aa > MAX_SAMPLES
+    {
+        return Err(format!(
+            "{}x{} characters with --aa {} are more than {} samples",
+            columns, rows, options.aa, MAX_SAMPLES
+        ));
+    }
+    // The image leaves out what is left over of a cell at the right and bottom
+    let (width, height) = (columns * cell_width, rows * cell_height);
+    let frames = match format {
+        Format::Png => 1,
+        Format::Gif => args.frames,
+    };
+    if u64::from(width) * u64::from(height) * u64::from(frames) > MAX_PIXELS {
+        return Err(format!(
+            "{} frames of {}x{} pixels are more than {} pixels",
+            frames, width, height, MAX_PIXELS
+        ));
+    }
+    // Character grid rendered off-screen through the same pipeline as the terminal
+    let mut renderer = Renderer::new(columns, rows, options.aa, theme.clone());
+    renderer.edges = options.edges;
+    renderer.labels = options.labels;
+
+    // Cells are square in pixel mode and glyph shaped otherwise
+    let camera = options
+        .camera
+        .camera(cell_height as f32 / cell_width as f32);
+    let mut scene = Scene {
+        camera,
+        lighting: options.lighting.lighting(&theme.faces),
+        ..Scene::new(cube.clone())
+    };
+
+    let (mut a, mut b, mut c) = args.angles.or(camera.fixed_angles()).unwrap_or((
+        rs_cube::START_ANGLES.0 + args.step.0,
+        rs_cube::START_ANGLES.1 + args.step.1,
+        rs_cube::START_ANGLES.2 + args.step.2,
+    ));
+    let step = match camera.fixed_angles() {
+        Some(_) => (0.0, 0.0, 0.0),
+        None => args.step,
+    };
+
+    let mut images: Vec<Vec<u8>> = Vec::with_capacity(frames as usize);
+    for _ in 0..frames {
+        scene.lighting.orbit(args.delay as f32 / 1000.0);
+        scene.angles = (a, b, c);
+        let frame = renderer.render(&scene);
+        images.push(rasterise(&frame, args, theme, cell_width, cell_height));
+
+        a += step.0;
+        b += step.1;
+        c += step.2;
+    }
+
+    let file = File::create(&args.out).map_err(|e| format!("{}: {}", args.out.display(), e))?;
+    let out = BufWriter::new(file);
+
+    match format {
+        Format::Png => png::write_rgb(out, width as u32, height as u32, &images[0]),
+        Format::Gif => write_gif(out, width, height, &images, args.delay),
+    }
+    .map_err(|e| format!("{}: {}", args.out.display(), e))
+}
+
+fn rasterise(
+    frame: &Frame,
+    args: &RenderArgs,
+    theme: &theme::Theme,
+    cell_width: u16,
+    cell_height: u16,
+) -> Vec<u8> {
+    let (cell_width, cell_height) = (cell_width as usize, cell_height as usize);
+    let width = frame.width as usize * cell_width;
+    let scale = args.scale as usize;
+    let background = theme.background.unwrap_or(BACKGROUND);
+    let mut image: Vec<u8> = background.repeat(width * frame.height as usize * cell_height);
+
+    for (index, &val) in frame.chars.iter().enumerate() {
+        if val == ' ' {
+            continue;
+        }
+        let mut rgb = color::to_rgb(frame.colors[index]);
+        let (cx, cy) = (
+            index % frame.width as usize * cell_width,
+            index / frame.width as usize * cell_height,
+        );
+
+        if args.pixels {
+            // Brightness follows the position of the character in the luminance ramp
+            let level = theme.ramp.iter().position(|&c| c == val).unwrap_or(0) as f32;
+            let shade = 0.25 + 0.75 * level / (theme.ramp.len() - 1).max(1) as f32;
+            rgb = rgb.map(|channel| (channel as f32 * shade) as u8);
+            image[(cy * width + cx) * 3..][..3].copy_from_slice(&rgb);
+            continue;
+        }
+
+        let glyph = font::glyph(val);
+        for y in 0..cell_height {
+            for x in 0..cell_width {
+                if font::is_set(glyph, x / scale, y / scale) {
+                    image[((cy + y) * width + cx + x) * 3..][..3].copy_from_slice(&rgb);
+                }
+            }
+        }
+    }
+    image
+}
+
+fn write_gif(
+    out: impl io::Write,
+    width: u16,
+    height: u16,
+    images: &[Vec<u8>],
+    delay: u32,
+) -> io::Result<()> {
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut palette_index: HashMap<[u8; 3], u8> = HashMap::new();
+    let mut frames: Vec<Vec<u8>> = Vec::with_capacity(images.len());
+
+    for image in images {
+        let mut indices = Vec::with_capacity(image.len() / 3);
+        for rgb in image.chunks(3) {
+            let rgb: [u8; 3] = [rgb[0], rgb[1], rgb[2]];
+            let index = match palette_index.get(&rgb) {
+                Some(&index) => index,
+                None => {
+                    if palette.len() == 256 {
+                        return Err(io::Error::other("more than 256 colours in animation"));
+                    }
+                    palette.push(rgb);
+                    palette_index.insert(rgb, (palette.len() - 1) as u8);
+                    (palette.len() - 1) as u8
+                }
+            };
+            indices.push(index);
+        }
+        frames.push(indices);
+    }
+
+    let mut encoder = gif::Encoder::new(out, width, height, &palette)?;
+    for indices in &frames {
+        encoder.write_frame(indices, u16::try_from(delay / 10).unwrap_or(u16::MAX))?;
+    }
+    encoder.finish()?.flush()
+}
@@ -1,157 +1,255 @@
+use crate::cube;
+use std::collections::HashMap;
+
 const CUBE_SIZE: f32 = 1.0; // Unit Cube
-const FRAC_CUBE_SIZE_2: f32 = CUBE_SIZE / 2.0;
-const FRAC_CUBE_SIZE_3: f32 = CUBE_SIZE / 3.0;
-const GRID_SPACING: f32 = 0.04;
-
-pub struct PointsAxisRange {
-    pub a: usize,
-    pub b: usize,
-    pub c: usize,
-}
+const CUBIE_SIZE: f32 = CUBE_SIZE / 3.0;
+// Space left between neighbouring cubies
+const CUBIE_GAP: f32 = 0.006;
+// Radius of the rounded cubie edges and corners
+const BEVEL_RADIUS: f32 = 0.02;
+// Black border between the edge of the flat part of a cubie face and its sticker
+const STICKER_BORDER: f32 = 0.004;
+const STICKER_CORNER_RADIUS: f32 = 0.03;
+
+#[derive(Clone, Copy)]
 pub struct Point3D {
     pub x: f32,
     pub y: f32,
     pub z: f32,
 }
 
-pub fn init(
-    points: &mut Vec<Point3D>,
-    points_color: &mut Vec<bool>,
-    points_axis_range: &mut PointsAxisRange,
-    spacing: f32,
-) {
-    // Axis A
-    {
-        // z
-        let k: f32 = FRAC_CUBE_SIZE_2;
-        // y
-        let mut i: f32 = -FRAC_CUBE_SIZE_2;
-        while i <= FRAC_CUBE_SIZE_2 {
-            // x
-            let mut j: f32 = -FRAC_CUBE_SIZE_2;
-            while j <= FRAC_CUBE_SIZE_2 {
-                points.push(Point3D { x: i, y: j, z: k });
-                points.push(Point3D { x: i, y: j, z: -k });
-
-                let mut char_color1: bool = true;
-                let mut char_color2: bool = true;
-                if i > (-FRAC_CUBE_SIZE_2 + FRAC_CUBE_SIZE_3) - GRID_SPACING
-                    && i < (-FRAC_CUBE_SIZE_2 + FRAC_CUBE_SIZE_3) + GRID_SPACING
-                {
-                    char_color1 = false;
-                    char_color2 = false;
-                } else if i > (FRAC_CUBE_SIZE_2 - FRAC_CUBE_SIZE_3) - GRID_SPACING
-                    && i < (FRAC_CUBE_SIZE_2 - FRAC_CUBE_SIZE_3) + GRID_SPACING
-                {
-                    char_color1 = false;
-                    char_color2 = false;
-                } else if j > (-FRAC_CUBE_SIZE_2 + FRAC_CUBE_SIZE_3) - GRID_SPACING
-                    && j < (-FRAC_CUBE_SIZE_2 + FRAC_CUBE_SIZE_3) + GRID_SPACING
-                {
-                    char_color1 = false;
-                    char_color2 = false;
-                } else if j > (FRAC_CUBE_SIZE_2 - FRAC_CUBE_SIZE_3) - GRID_SPACING
-                    && j < (FRAC_CUBE_SIZE_2 - FRAC_CUBE_SIZE_3) + GRID_SPACING
-                {
-                    char_color1 = false;
-                    char_color2 = false;
-                }
-                points_color.push(char_color1);
-                points_color.push(char_color2);
+// Sampled surface of the 26 visible cubies. Every point has a unit normal from the shared
+// `normals` table, the facelet of its sticker if it is on one
+// and the position of its cubie, which decides whether it moves in a layer turn
+#[derive(Default)]
+pub struct Model {
+    // Positions are kept as separate coordinate arrays so they can be transformed in batches
+    pub points_x: Vec<f32>,
+    pub points_y: Vec<f32>,
+    pub points_z: Vec<f32>,
+    pub points_normal: Vec<u16>,
+    pub points_color: Vec<bool>, // false for the plastic body
+    // Facelet of the outer face a point belongs to, also set around the sticker and on the
+    // rounded edges so stickerless cubes can be coloured all the way to the edges
+    pub points_facelet: Vec<Option<u8>>,
+    pub points_cubie: Vec<[i8; 3]>,
+    // Points facing another cubie, only visible while a layer is turning
+    pub points_internal: Vec<bool>,
+    pub normals: Vec<Point3D>,
+    normal_index: HashMap<[i32; 3], u16>,
+}
 
-                j += spacing;
-            }
-            i += spacing;
+impl Model {
+    pub fn clear(&mut self) {
+        *self = Model::default();
+    }
+
+    pub fn len(&self) -> usize {
+        self.points_x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points_x.is_empty()
+    }
+
+    pub fn point(&self, index: usize) -> Point3D {
+        Point3D {
+            x: self.points_x[index],
+            y: self.points_y[index],
+            z: self.points_z[index],
         }
-        points_axis_range.a = points.len();
     }
-    // Axis B
-    {
-        // y
-        let i: f32 = FRAC_CUBE_SIZE_2;
-        // x
-        let mut j: f32 = -FRAC_CUBE_SIZE_2;
-        while j <= FRAC_CUBE_SIZE_2 {
-            let mut k: f32 = -FRAC_CUBE_SIZE_2;
-            while k <= FRAC_CUBE_SIZE_2 {
-                points.push(Point3D { x: i, y: j, z: k });
-                points.push(Point3D { x: -i, y: j, z: k });
-
-                let mut char_color1: bool = true;
-                let mut char_color2: bool = true;
-                if j > (-FRAC_CUBE_SIZE_2 + FRAC_CUBE_SIZE_3) - GRID_SPACING
-                    && j < (-FRAC_CUBE_SIZE_2 + FRAC_CUBE_SIZE_3) + GRID_SPACING
-                {
-                    char_color1 = false;
-                    char_color2 = false;
-                } else if j > (FRAC_CUBE_SIZE_2 - FRAC_CUBE_SIZE_3) - GRID_SPACING
-                    && j < (FRAC_CUBE_SIZE_2 - FRAC_CUBE_SIZE_3) + GRID_SPACING
-                {
-                    char_color1 = false;
-                    char_color2 = false;
-                } else if k > (-FRAC_CUBE_SIZE_2 + FRAC_CUBE_SIZE_3) - GRID_SPACING
-                    && k < (-FRAC_CUBE_SIZE_2 + FRAC_CUBE_SIZE_3) + GRID_SPACING
-                {
-                    char_color1 = false;
-                    char_color2 = false;
-                } else if k > (FRAC_CUBE_SIZE_2 - FRAC_CUBE_SIZE_3) - GRID_SPACING
-                    && k < (FRAC_CUBE_SIZE_2 - FRAC_CUBE_SIZE_3) + GRID_SPACING
-                {
-                    char_color1 = false;
-                    char_color2 = false;
+
+    // Adds a point given in cube coordinates
+    fn push(
+        &mut self,
+        pos: [f32; 3],
+        normal: [f32; 3],
+        cubie: [i8; 3],
+        facelet: Option<u8>,
+        sticker: bool,
+    ) {
+        // Nearby normals share an entry so lighting can be computed once per table entry
+        let key = normal.map(|n| (n * 16.0).round() as i32);
+        let next = self.normal_index.len() as u16;
+        let index = *self.normal_index.entry(key).or_insert(next);
+        if index == next {
+            self.normals.push(to_point(normal));
+        }
+
+        let internal = (0..3).all(|axis| {
+            normal[axis].abs() < 1e-3 || (cubie[axis] + normal[axis].signum() as i8).abs() <= 1
+        });
+
+        let point = to_point(pos);
+        self.points_x.push(point.x);
+        self.points_y.push(point.y);
+        self.points_z.push(point.z);
+        self.points_normal.push(index);
+        self.points_color.push(sticker);
+        self.points_facelet.push(facelet);
+        self.points_cubie.push(cubie);
+        self.points_internal.push(internal);
+    }
+}
+
+// Points are stored as (x, y, z) = (front, left, down) in cube coordinates
+pub fn to_point(pos: [f32; 3]) -> Point3D {
+    Point3D {
+        x: pos[2],
+        y: -pos[0],
+        z: -pos[1],
+    }
+}
+
+pub fn to_cube(point: &Point3D) -> [f32; 3] {
+    [-point.y, -point.z, point.x]
+}
+
+// Evenly spaced values from -half to +half, at most `spacing` apart
+fn samples(half: f32, spacing: f32) -> impl Iterator<Item = f32> {
+    let steps = ((2.0 * half / spacing).ceil() as usize).max(1);
+    (0..=steps).map(move |step| -half + 2.0 * half * step as f32 / steps as f32)
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let mag = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    v.map(|c| c / mag)
+}
+
+fn is_on_sticker(u: f32, v: f32, half: f32) -> bool {
+    let inner = half - STICKER_CORNER_RADIUS;
+    let du = (u.abs() - inner).max(0.0);
+    let dv = (v.abs() - inner).max(0.0);
+    u.abs() <= half && v.abs() <= half && du * du + dv * dv <= STICKER_CORNER_RADIUS.powi(2)
+}
+
+// Facelet of the outer face of a cubie closest to the direction of `normal`, given the facelets
+// of the cubie along each axis
+fn outer_facelet(facelets: [Option<u8>; 3], cubie: [i8; 3], normal: [f32; 3]) -> Option<u8> {
+    let axis = (0..3)
+        .filter(|&axis| normal[axis].abs() > 1e-3 && cubie[axis] as f32 * normal[axis] > 0.0)
+        .max_by(|&a, &b| normal[a].abs().total_cmp(&normal[b].abs()))?;
+    facelets[axis]
+}
+
+// A cubie is a box with its edges and corners rounded off, i.e. a smaller box grown by
+// `BEVEL_RADIUS` in every direction
+fn add_cubie(model: &mut Model, cubie: [i8; 3], spacing: f32) {
+    let center = cubie.map(|c| c as f32 * CUBIE_SIZE);
+    let half = (CUBIE_SIZE - CUBIE_GAP) / 2.0;
+    let inner = half - BEVEL_RADIUS;
+    let sticker_half = inner - STICKER_BORDER;
+    let arc_steps = ((BEVEL_RADIUS * std::f32::consts::FRAC_PI_2 / spacing).ceil() as usize).max(1);
+    let facelets = [0, 1, 2].map(|axis| {
+        let mut normal = [0i8; 3];
+        normal[axis] = cubie[axis];
+        (cubie[axis] != 0).then(|| cube::facelet_from_geometry(cubie, normal) as u8)
+    });
+
+    // Flat faces
+    for axis in 0..3 {
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        for sign in [-1i8, 1] {
+            let mut normal = [0i8; 3];
+            normal[axis] = sign;
+            let outer = cubie[axis] == sign;
+            let facelet = facelets[axis].filter(|_| outer);
+
+            for u in samples(inner, spacing) {
+                for v in samples(inner, spacing) {
+                    let mut pos = center;
+                    pos[axis] += sign as f32 * half;
+                    pos[u_axis] += u;
+                    pos[v_axis] += v;
+                    let sticker = outer && is_on_sticker(u, v, sticker_half);
+                    model.push(pos, normal.map(|n| n as f32), cubie, facelet, sticker);
                 }
-                points_color.push(char_color1);
-                points_color.push(char_color2);
+            }
+        }
+    }
 
-                k += spacing;
+    // Rounded edges, a quarter cylinder along each of the 12 edges
+    for axis in 0..3 {
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        for (u_sign, v_sign) in [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+            for step in 0..=arc_steps {
+                let angle = std::f32::consts::FRAC_PI_2 * step as f32 / arc_steps as f32;
+                let mut normal = [0.0; 3];
+                normal[u_axis] = u_sign * angle.cos();
+                normal[v_axis] = v_sign * angle.sin();
+
+                for t in samples(inner, spacing) {
+                    let mut pos = center;
+                    pos[axis] += t;
+                    pos[u_axis] += u_sign * inner + BEVEL_RADIUS * normal[u_axis];
+                    pos[v_axis] += v_sign * inner + BEVEL_RADIUS * normal[v_axis];
+                    model.push(
+                        pos,
+                        normal,
+                        cubie,
+                        outer_facelet(facelets, cubie, normal),
+                        false,
+                    );
+                }
             }
-            j += spacing;
         }
-        points_axis_range.b = points.len();
     }
 
-    // Axis C
-    {
-        // x
-        let j: f32 = FRAC_CUBE_SIZE_2;
-        // z
-        let mut k: f32 = -FRAC_CUBE_SIZE_2;
-        while k <= FRAC_CUBE_SIZE_2 {
-            let mut i: f32 = -FRAC_CUBE_SIZE_2;
-            while i <= FRAC_CUBE_SIZE_2 {
-                points.push(Point3D { x: i, y: j, z: k });
-                points.push(Point3D { x: i, y: -j, z: k });
-
-                let mut char_color1: bool = true;
-                let mut char_color2: bool = true;
-                if k > (-FRAC_CUBE_SIZE_2 + FRAC_CUBE_SIZE_3) - GRID_SPACING
-                    && k < (-FRAC_CUBE_SIZE_2 + FRAC_CUBE_SIZE_3) + GRID_SPACING
-                {
-                    char_color1 = false;
-                    char_color2 = false;
-                } else if k > (FRAC_CUBE_SIZE_2 - FRAC_CUBE_SIZE_3) - GRID_SPACING
-                    && k < (FRAC_CUBE_SIZE_2 - FRAC_CUBE_SIZE_3) + GRID_SPACING
-                {
-                    char_color1 = false;
-                    char_color2 = false;
-                } else if i > (-FRAC_CUBE_SIZE_2 + FRAC_CUBE_SIZE_3) - GRID_SPACING
-                    && i < (-FRAC_CUBE_SIZE_2 + FRAC_CUBE_SIZE_3) + GRID_SPACING
-                {
-                    char_color1 = false;
-                    char_color2 = false;
-                } else if i > (FRAC_CUBE_SIZE_2 - FRAC_CUBE_SIZE_3) - GRID_SPACING
-                    && i < (FRAC_CUBE_SIZE_2 - FRAC_CUBE_SIZE_3) + GRID_SPACING
-                {
-                    char_color1 = false;
-                    char_color2 = false;
+    // Rounded corners, an eighth of a sphere at each of the 8 corners, sampled as three
+    // patches of directions around the diagonal
+    for signs in (0..8).map(|i| [i & 1, i >> 1 & 1, i >> 2 & 1].map(|b| (b * 2 - 1) as f32)) {
+        for axis in 0..3 {
+            for i in 0..=arc_steps {
+                for j in 0..=arc_steps {
+                    let mut dir = [0.0; 3];
+                    dir[axis] = 1.0;
+                    dir[(axis + 1) % 3] = i as f32 / arc_steps as f32;
+                    dir[(axis + 2) % 3] = j as f32 / arc_steps as f32;
+                    let normal = normalize([0, 1, 2].map(|a| dir[a] * signs[a]));
+
+                    let pos =
+                        [0, 1, 2].map(|a| center[a] + signs[a] * inner + BEVEL_RADIUS * normal[a]);
+                    model.push(
+                        pos,
+                        normal,
+                        cubie,
+                        outer_facelet(facelets, cubie, normal),
+                        false,
+                    );
                 }
-                points_color.push(char_color1);
-                points_color.push(char_color2);
+            }
+        }
+    }
+}
 
-                i += spacing;
+pub fn init(model: &mut Model, spacing: f32) {
+    for x in -1..=1 {
+        for y in -1..=1 {
+            for z in -1..=1 {
+                // The core is never visible
+                if (x, y, z) != (0, 0, 0) {
+                    add_cubie(model, [x, y, z], spacing);
+                }
             }
-            k += spacing;
         }
-        points_axis_range.c = points.len();
     }
 }
+
+// Centre of the sticker of a facelet on the surface of its cubie
+pub fn sticker_center(facelet: usize) -> Point3D {
+    let (cubie, normal) = cube::facelet_geometry(facelet);
+    let half = (CUBIE_SIZE - CUBIE_GAP) / 2.0;
+    to_point([0, 1, 2].map(|axis| cubie[axis] as f32 * CUBIE_SIZE + normal[axis] as f32 * half))
+}
+
+// Rotates a point given as (front, left, down) around a cube axis, counter-clockwise by the
+// angle with sine `sin` and cosine `cos` when looking down the positive axis
+pub fn rotate_about(point: &Point3D, axis: usize, sin: f32, cos: f32) -> Point3D {
+    let pos = to_cube(point);
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let mut rotated = pos;
+    rotated[u] = pos[u] * cos - pos[v] * sin;
+    rotated[v] = pos[u] * sin + pos[v] * cos;
+    to_point(rotated)
+}
@@ -0,0 +1,140 @@
+use libc::{
+    isatty, poll, pollfd, read, tcgetattr, tcsetattr, termios, ECHO, ICANON, POLLIN, STDIN_FILENO,
+    TCSANOW, VMIN, VTIME,
+};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Terminal settings to restore on exit, set while key presses are being read
+static ORIGINAL_TERMIOS: Mutex<Option<termios>> = Mutex::new(None);
+
+// Switches stdin to unbuffered, non-blocking reads without echo. Signals such as ^C are
+// still delivered
+pub fn enable_raw_mode() {
+    unsafe {
+        if isatty(STDIN_FILENO) == 0 {
+            return;
+        }
+        let mut original: termios = std::mem::zeroed();
+        if tcgetattr(STDIN_FILENO, &mut original) != 0 {
+            return;
+        }
+        let mut raw = original;
+        raw.c_lflag &= !(ICANON | ECHO);
+        raw.c_cc[VMIN] = 0;
+        raw.c_cc[VTIME] = 0;
+        if tcsetattr(STDIN_FILENO, TCSANOW, &raw) == 0 {
+            *ORIGINAL_TERMIOS.lock().unwrap() = Some(original);
+        }
+    }
+}
+
+pub fn disable_raw_mode() {
+    if let Some(original) = ORIGINAL_TERMIOS.lock().unwrap().take() {
+        unsafe {
+            tcsetattr(STDIN_FILENO, TCSANOW, &original);
+        }
+    }
+}
+
+// Returns the keys pressed since the last call. Escape sequences such as arrow keys are
+// dropped whole so their bytes are not mistaken for other keys
+pub fn read_keys() -> Vec<u8> {
+    if ORIGINAL_TERMIOS.lock().unwrap().is_none() {
+        return Vec::new();
+    }
+    let mut buf = [0u8; 64];
+    let n = unsafe { read(STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len()) };
+    if n <= 0 {
+        return Vec::new();
+    }
+    without_escapes(&buf[..n as usize])
+}
+
+// The keys with every escape sequence left out: CSI sequences such as arrows up to their
+// final byte, SS3 sequences such as F1 with the byte after them, and Alt with its key
+fn without_escapes(bytes: &[u8]) -> Vec<u8> {
+    let mut keys = Vec::with_capacity(bytes.len());
+    let mut rest = bytes.iter().copied();
+    while let Some(byte) = rest.next() {
+        if byte != 0x1B {
+            keys.push(byte);
+            continue;
+        }
+        match rest.clone().next() {
+            Some(b'[') => {
+                rest.next();
+                // Parameters and intermediates come before a final byte in @ to ~
+                for byte in rest.by_ref() {
+                    if (0x40..=0x7E).contains(&byte) {
+                        break;
+                    }
+                }
+            }
+            Some(b'O') => {
+                rest.nth(1);
+            }
+            // Escape pressed twice, the second one starts over
+            Some(0x1B) | None => {}
+            Some(_) => {
+                rest.next();
+            }
+        }
+    }
+    keys
+}
+
+// Reads the terminal's reply to a query until `done` accepts what has arrived or the timeout
+// runs out. Keys pressed meanwhile end up in the reply
+pub fn read_reply(timeout: Duration, done: impl Fn(&[u8]) -> bool) -> Vec<u8> {
+    let mut reply = Vec::new();
+    if ORIGINAL_TERMIOS.lock().unwrap().is_none() {
+        return reply;
+    }
+    let deadline = Instant::now() + timeout;
+    while !done(&reply) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        let mut fds = pollfd {
+            fd: STDIN_FILENO,
+            events: POLLIN,
+            revents: 0,
+        };
+        let mut buf = [0u8; 64];
+        let n = unsafe {
+            if poll(&mut fds, 1, left.as_millis().max(1) as i32) <= 0 {
+                continue;
+            }
+            read(STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len())
+        };
+        if n <= 0 {
+            break;
+        }
+        reply.extend_from_slice(&buf[..n as usize]);
+    }
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_sequences_are_left_out() {
+        let cases: [(&[u8], &[u8]); 8] = [
+            (b"ab", b"ab"),
+            (b"a\x1B[Ab", b"ab"),
+            (b"\x1B[A\x1B[Bq", b"q"),
+            (b"\x1B[1;5Cq", b"q"),
+            (b"\x1BOPq", b"q"),
+            (b"\x1Bxq", b"q"),
+            (b"\x1B\x1B[Dq", b"q"),
+            (b"q\x1B[1;", b"q"),
+        ];
+        for (bytes, keys) in cases {
+            assert_eq!(without_escapes(bytes), keys, "{:?}", bytes);
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use rs_cube::ansi_escape_code::SetCursorPos;
+use rs_cube::camera::CameraAction;
+use rs_cube::cube::{self, Move};
+use rs_cube::Theme;
+use std::io::{self, Write};
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Preset {
+    // Camera keys only
+    Standard,
+    // Keyboard turning as in csTimer's virtual cube
+    Cstimer,
+    // hjkl turn the whole cube, letters turn faces and shift turns them back
+    Vim,
+    // Arrows turn the whole cube, the corners the top and bottom layers
+    Numpad,
+}
+
+// Bindings every preset starts from
+const STANDARD: [(u8, &str); 3] = [(b'0', "reset-camera"), (b'q', "quit"), (b'?', "help")];
+
+const CSTIMER: [(u8, &str); 30] = [
+    (b'i', "R"),
+    (b'k', "R'"),
+    (b'j', "U"),
+    (b'f', "U'"),
+    (b'h', "F"),
+    (b'g', "F'"),
+    (b'd', "L"),
+    (b'e', "L'"),
+    (b's', "D"),
+    (b'l', "D'"),
+    (b'w', "B"),
+    (b'o', "B'"),
+    (b'u', "r"),
+    (b'm', "r'"),
+    (b'v', "l"),
+    (b'r', "l'"),
+    (b'z', "d"),
+    (b'/', "d'"),
+    (b'5', "M"),
+    (b'6', "M"),
+    (b'x', "M'"),
+    (b'.', "M'"),
+    (b't', "x"),
+    (b'y', "x"),
+    (b'b', "x'"),
+    (b'n', "x'"),
+    (b';', "y"),
+    (b'a', "y'"),
+    (b'p', "z"),
+    // csTimer has z' on q, which stays quit
+    (b'Q', "z'"),
+];
+
+const VIM: [(u8, &str); 16] = [
+    (b'h', "y"),
+    (b'l', "y'"),
+    (b'k', "x"),
+    (b'j', "x'"),
+    (b'r', "R"),
+    (b'R', "R'"),
+    (b'u', "U"),
+    (b'U', "U'"),
+    (b'f', "F"),
+    (b'F', "F'"),
+    (b'd', "D"),
+    (b'D', "D'"),
+    (b'b', "B"),
+    (b'B', "B'"),
+    // l is taken by y'
+    (b'g', "L"),
+    (b'G', "L'"),
+];
+
+const NUMPAD: [(u8, &str); 13] = [
+    (b'8', "x"),
+    (b'2', "x'"),
+    (b'4', "y"),
+    (b'6', "y'"),
+    (b'7', "U"),
+    (b'9', "U'"),
+    (b'1', "D'"),
+    (b'3', "D"),
+    (b'5', "F"),
+    (b'0', "F'"),
+    (b'/', "z'"),
+    (b'*', "z"),
+    (b'.', "reset-cube"),
+];
+
+const CAMERA_ACTIONS: [(CameraAction, &str, &str); 7] = [
+    (CameraAction::CycleProjection, "projection", "projection"),
+    (CameraAction::NarrowFov, "narrow-fov", "narrow fov"),
+    (CameraAction::WidenFov, "widen-fov", "widen fov"),
+    (CameraAction::Closer, "closer", "closer"),
+    (CameraAction::Farther, "farther", "farther"),
+    (CameraAction::DecreaseAspect, "decrease-aspect", "aspect -"),
+    (CameraAction::IncreaseAspect, "increase-aspect", "aspect +"),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Move(Move),
+    Camera(CameraAction),
+    ResetCamera,
+    // Back to the starting state
+    ResetCube,
+    // Show or hide the bindings
+    Help,
+    Quit,
+}
+
+impl Action {
+    fn label(self) -> String {
+        match self {
+            Action::Move(m) => m.to_string(),
+            Action::Camera(action) => {
+                let (_, _, label) = CAMERA_ACTIONS
+                    .iter()
+                    .find(|(a, _, _)| *a == action)
+                    .unwrap();
+                label.to_string()
+            }
+            Action::ResetCamera => "reset camera".to_string(),
+            Action::ResetCube => "reset cube".to_string(),
+            Action::Help => "help".to_string(),
+            Action::Quit => "quit".to_string(),
+        }
+    }
+}
+
+// A move in standard notation, a camera action, a command or "none" to take a key away
+fn parse_action(s: &str) -> Result<Option<Action>, String> {
+    let command = match s {
+        "none" => return Ok(None),
+        "reset-camera" => Some(Action::ResetCamera),
+        "reset-cube" => Some(Action::ResetCube),
+        "help" => Some(Action::Help),
+        "quit" => Some(Action::Quit),
+        _ => CAMERA_ACTIONS
+            .iter()
+            .find(|(_, name, _)| *name == s)
+            .map(|&(action, _, _)| Action::Camera(action)),
+    };
+    if command.is_some() {
+        return Ok(command);
+    }
+    match cube::parse_alg(s) {
+        Ok(moves) if moves.len() == 1 => Ok(Some(Action::Move(moves[0]))),
+        _ => Err(format!(
+            "unknown action \"{}\", expected a move, none, reset-camera, reset-cube, help, quit \
+             or one of {}",
+            s,
+            CAMERA_ACTIONS.map(|(_, name, _)| name).join(", ")
+        )),
+    }
+}
+
+// A printable character, or space, tab or enter
+fn parse_key(s: &str) -> Result<u8, String> {
+    match s {
+        "space" => Ok(b' '),
+        "tab" => Ok(b'\t'),
+        "enter" => Ok(b'\n'),
+        _ if s.len() == 1 && s.as_bytes()[0].is_ascii_graphic() => Ok(s.as_bytes()[0]),
+        _ => Err(format!(
+            "unknown key \"{}\", expected a single character, space, tab or enter",
+            s
+        )),
+    }
+}
+
+fn key_name(key: u8) -> String {
+    match key {
+        b' ' => "space".to_string(),
+        b'\t' => "tab".to_string(),
+        b'\n' => "enter".to_string(),
+        key => (key as char).to_string(),
+    }
+}
+
+// Checks a binding from the config file
+pub fn parse_binding(key: &str, action: &str) -> Result<(), String> {
+    parse_key(key)?;
+    parse_action(action)?;
+    Ok(())
+}
+
+// Keys and what they do in the interactive mode
+pub struct Bindings {
+    keys: Vec<(u8, Action)>,
+}
+
+impl Bindings {
+    // The preset with the bindings from the config file on top, which are checked already
+    pub fn new(preset: Preset, overrides: &[(String, String)]) -> Self {
+        let mut bindings = Bindings { keys: Vec::new() };
+        for (key, action) in CameraAction::KEYS {
+            bindings.bind(key, Some(Action::Camera(action)));
+        }
+        let preset: &[(u8, &str)] = match preset {
+            Preset::Standard => &[],
+            Preset::Cstimer => &CSTIMER,
+            Preset::Vim => &VIM,
+            Preset::Numpad => &NUMPAD,
+        };
+        for &(key, action) in STANDARD.iter().chain(preset) {
+            bindings.bind(key, parse_action(action).unwrap());
+        }
+        for (key, action) in overrides {
+            bindings.bind(parse_key(key).unwrap(), parse_action(action).unwrap());
+        }
+        bindings
+    }
+
+    fn bind(&mut self, key: u8, action: Option<Action>) {
+        self.keys.retain(|&(k, _)| k != key);
+        if let Some(action) = action {
+            self.keys.push((key, action));
+        }
+    }
+
+    // What a key does. Capitals do what their letter does unless they have their own binding
+    pub fn action(&self, key: u8) -> Option<Action> {
+        let find = |key: u8| self.keys.iter().find(|&&(k, _)| k == key);
+        find(key)
+            .or_else(|| find(key.to_ascii_lowercase()))
+            .map(|&(_, action)| action)
+    }
+
+    // Lines of the help overlay: moves, camera actions and commands under their headings, each
+    // action with all of its keys, laid out in columns to fit the width
+    fn help_lines(&self, width: usize) -> Vec<String> {
+        let mut sections: [(&str, Vec<(String, Action)>); 3] = [
+            ("Moves", Vec::new()),
+            ("Camera", Vec::new()),
+            ("Commands", Vec::new()),
+        ];
+        for &(key, action) in &self.keys {
+            let section = match action {
+                Action::Move(_) => 0,
+                Action::Camera(_) => 1,
+                _ => 2,
+            };
+            let entries = &mut sections[section].1;
+            match entries.iter_mut().find(|(_, a)| *a == action) {
+                Some((keys, _)) => *keys = format!("{} {}", keys, key_name(key)),
+                None => entries.push((key_name(key), action)),
+            }
+        }
+        if !self.keys.iter().any(|&(_, action)| action == Action::Quit) {
+            sections[2].1.push(("^C".to_string(), Action::Quit));
+        }
+
+        let entries = sections.iter().flat_map(|(_, entries)| entries);
+        let keys_width = entries
+            .clone()
+            .map(|(keys, _)| keys.len())
+            .max()
+            .unwrap_or(0);
+        let entry_width = entries
+            .map(|(_, action)| keys_width + 1 + action.label().len())
+            .max()
+            .unwrap_or(0)
+            + 2;
+        let columns = ((width.saturating_sub(2)) / entry_width).max(1);
+
+        let mut lines = Vec::new();
+        for (heading, entries) in &sections {
+            if entries.is_empty() {
+                continue;
+            }
+            lines.push(format!(" {}", heading));
+            for row in entries.chunks(columns) {
+                let mut line = String::from(" ");
+                for (keys, action) in row {
+                    let entry = format!("{:>w$} {}", keys, action.label(), w = keys_width);
+                    line.push_str(&format!("{:<w$}", entry, w = entry_width));
+                }
+                lines.push(line);
+            }
+        }
+        let box_width = lines.iter().map(|line| line.len()).max().unwrap_or(0) + 1;
+        lines
+            .into_iter()
+            .map(|line| format!("{:<w$}", line, w = box_width.min(width)))
+            .map(|line| line.chars().take(width).collect())
+            .collect()
+    }
+
+    // Draws the help overlay over the bottom left corner of a width x height view
+    pub fn draw_help(
+        &self,
+        mut handle: impl Write,
+        theme: &Theme,
+        use_color: bool,
+        width: u16,
+        height: u16,
+    ) -> io::Result<()> {
+        let lines = self.help_lines(width as usize);
+        let skip = lines.len().saturating_sub(height as usize);
+        let top = height as usize - (lines.len() - skip);
+        if use_color {
+            write!(handle, "{}", theme.blank)?;
+        }
+        for (row, line) in lines.iter().skip(skip).enumerate() {
+            write!(
+                handle,
+                "{}{}",
+                SetCursorPos((top + row) as u16 + 1, 1),
+                line
+            )?;
+        }
+        Ok(())
+    }
+}
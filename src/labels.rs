@@ -0,0 +1,107 @@
+use crate::camera::Camera;
+use crate::cube::{self, Cube, Face};
+use crate::supersample::Samples;
+use crate::theme::Theme;
+use crate::{init, rotate, Turn};
+
+// Distance in front of a sticker centre at which another surface hides it
+const HIDDEN_DEPTH: f32 = 0.15;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Labels {
+    // The face letter of the sticker's colour
+    Letters,
+    // A shape per colour
+    Symbols,
+}
+
+impl Labels {
+    pub fn glyph(self, face: Face) -> char {
+        match self {
+            Labels::Letters => face.to_char(),
+            Labels::Symbols => match face {
+                Face::U => 'o',
+                Face::R => 'x',
+                Face::F => '+',
+                Face::D => '%',
+                Face::L => '&',
+                Face::B => '^',
+            },
+        }
+    }
+}
+
+// The frame labels are drawn into and how it was projected
+pub(crate) struct Context<'a> {
+    pub buffer: &'a mut [char],
+    pub cbuffer: &'a mut [&'static str],
+    // Samples the frame was resolved from, giving its size and depths
+    pub samples: &'a Samples,
+    pub camera: &'a Camera,
+    pub trig_values: &'a [f32],
+}
+
+// Writes the label of every visible sticker over the character at its centre, so stickers can
+// be told apart without relying on colour
+pub(crate) fn draw(
+    context: Context,
+    cube: &Cube,
+    turn: Option<Turn>,
+    theme: &Theme,
+    labels: Labels,
+) {
+    let Context {
+        buffer,
+        cbuffer,
+        samples,
+        camera,
+        trig_values,
+    } = context;
+    let factor = samples.factor as usize;
+    let width = samples.width as usize / factor;
+
+    for facelet in 0..54 {
+        let (cubie, normal) = cube::facelet_geometry(facelet);
+        let mut center = init::sticker_center(facelet);
+        let mut normal = init::to_point(normal.map(|n| n as f32));
+        if let Some(Turn {
+            axis,
+            layers,
+            angle,
+        }) = turn
+        {
+            if layers & (1 << (cubie[axis] + 1)) != 0 {
+                let (sin, cos) = angle.sin_cos();
+                center = init::rotate_about(&center, axis, sin, cos);
+                normal = init::rotate_about(&normal, axis, sin, cos);
+            }
+        }
+
+        let rotated = rotate(center.x, center.y, center.z, trig_values);
+        let normal = rotate(normal.x, normal.y, normal.z, trig_values);
+        if normal.dot(&camera.view_direction(&rotated)) <= 0.0 {
+            continue;
+        }
+        let (x, y, ooz) = camera.project(
+            rotated.x,
+            rotated.y,
+            rotated.z,
+            samples.width,
+            samples.height,
+        );
+        if x < 0.0 || y < 0.0 || x >= samples.width as f32 || y >= samples.height as f32 {
+            continue;
+        }
+        // Covered by a turning layer. Sloped faces can be nearer than the centre elsewhere in
+        // the same sample, so only surfaces well in front count
+        let sample = y as usize * samples.width as usize + x as usize;
+        if 1.0 / ooz - 1.0 / samples.zbuffer[sample] > HIDDEN_DEPTH {
+            continue;
+        }
+
+        let face = cube.facelets[facelet];
+        let cell = y as usize / factor * width + x as usize / factor;
+        buffer[cell] = labels.glyph(face);
+        cbuffer[cell] = theme.faces[face as usize];
+    }
+}
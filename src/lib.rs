@@ -0,0 +1,128 @@
+// Copyright (c) 2023 doprz
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Renders a shaded, rotating Rubik's cube into a grid of coloured characters, so it can be
+//! embedded in other terminal programs. A [`Renderer`] draws a [`Scene`] into a [`Frame`].
+pub mod ansi_escape_code;
+pub mod camera;
+pub mod color;
+pub mod cube;
+mod edges;
+mod encoder;
+pub mod init;
+pub mod labels;
+pub mod light;
+pub mod renderer;
+mod supersample;
+pub mod theme;
+pub mod transform;
+#[cfg(feature = "ratatui")]
+pub mod widget;
+
+pub use camera::Camera;
+pub use cube::Cube;
+pub use light::{Light, Lighting};
+pub use renderer::{Frame, Renderer, Scene, Turn};
+pub use theme::Theme;
+#[cfg(feature = "ratatui")]
+pub use widget::CubeWidget;
+
+/// Edge length of the cube
+pub const CUBE_SIZE: f32 = 1.0;
+
+/// Rotation angles of the cube before it starts turning
+pub const START_ANGLES: (f32, f32, f32) = (
+    -std::f32::consts::FRAC_PI_2,
+    -std::f32::consts::FRAC_PI_2,
+    std::f32::consts::FRAC_PI_2 + std::f32::consts::FRAC_PI_4,
+);
+
+/// A point or direction, with x right, y up and z away from the viewer
+#[derive(Clone, Copy)]
+pub struct Vector3f {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+impl Vector3f {
+    fn mag(&self) -> f32 {
+        let x: f32 = self.x;
+        let y: f32 = self.y;
+        let z: f32 = self.z;
+
+        (x * x + y * y + z * z).sqrt()
+    }
+
+    fn norm(&mut self) {
+        let x: f32 = self.x;
+        let y: f32 = self.y;
+        let z: f32 = self.z;
+
+        let mag: f32 = self.mag();
+        let oomag: f32 = 1.0 / mag;
+
+        if mag > 0.0 {
+            self.x = x * oomag;
+            self.y = y * oomag;
+            self.z = z * oomag;
+        }
+    }
+
+    fn dot(&self, vec: &Vector3f) -> f32 {
+        self.x * vec.x + self.y * vec.y + self.z * vec.z
+    }
+
+    fn rot(&mut self, trig_values: &[f32]) {
+        let trig_values = &trig_values[..6];
+        let (sin_a, sin_b, sin_c) = (&trig_values[0], &trig_values[2], &trig_values[4]);
+        let (cos_a, cos_b, cos_c) = (&trig_values[1], &trig_values[3], &trig_values[5]);
+
+        let x_arg1 = cos_a * sin_b * sin_c - sin_a * cos_c;
+        let x_arg2 = cos_a * sin_b * cos_c + sin_a * sin_c;
+        let y_arg1 = sin_a * sin_b * sin_c + cos_a * cos_c;
+        let y_arg2 = sin_a * sin_b * cos_c - cos_a * sin_c;
+
+        let prev = *self;
+
+        self.x = cos_a * cos_b * prev.x + (x_arg1) * prev.y + (x_arg2) * prev.z;
+        self.y = sin_a * cos_b * prev.x + (y_arg1) * prev.y + (y_arg2) * prev.z;
+        self.z = -prev.x * sin_b + prev.y * cos_b * sin_c + prev.z * cos_b * cos_c;
+    }
+}
+
+// Rotates a point by the cube's angles
+fn rotate(i: f32, j: f32, k: f32, trig_values: &[f32]) -> Vector3f {
+    let trig_values = &trig_values[..6];
+
+    let (sin_a, sin_b, sin_c) = (trig_values[0], trig_values[2], trig_values[4]);
+    let (cos_a, cos_b, cos_c) = (trig_values[1], trig_values[3], trig_values[5]);
+
+    let cos_a_sin_b = cos_a * sin_b;
+    let sin_a_sin_b = sin_a * sin_b;
+
+    let x: f32 = cos_a * cos_b * j
+        + (cos_a_sin_b * sin_c - sin_a * cos_c) * i
+        + (cos_a_sin_b * cos_c + sin_a * sin_c) * k;
+    let y: f32 = sin_a * cos_b * j
+        + (sin_a_sin_b * sin_c + cos_a * cos_c) * i
+        + (sin_a_sin_b * cos_c - cos_a * sin_c) * k;
+    let z: f32 = -j * sin_b + i * cos_b * sin_c + k * cos_b * cos_c;
+
+    Vector3f { x, y, z }
+}
+
+/// Rotates the point (i, j, k) by the angles in `trig_values`, sin and cos of each angle in
+/// turn, and projects it onto a `width` x `height` screen, returning (x, y, 1 / z)
+pub fn project(
+    i: f32,
+    j: f32,
+    k: f32,
+    width: u16,
+    height: u16,
+    trig_values: &[f32],
+    camera: &camera::Camera,
+) -> (f32, f32, f32) {
+    let point = rotate(i, j, k, trig_values);
+    camera.project(point.x, point.y, point.z, width, height)
+}
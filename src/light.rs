@@ -0,0 +1,162 @@
+use crate::cube::Face;
+use crate::{color, Vector3f};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LightKind {
+    // Infinitely far away, shining along the same direction everywhere
+    Directional,
+    Point,
+}
+
+#[derive(Clone, Copy)]
+pub struct Light {
+    kind: LightKind,
+    // Direction towards a directional light or the position of a point light, with x right,
+    // y up and z away from the viewer
+    position: Vector3f,
+    color: [f32; 3],
+}
+
+const DEFAULT_LIGHT: (f32, f32, f32) = (0.0, 1.0, -1.0);
+pub const WHITE: [f32; 3] = [1.0, 1.0, 1.0];
+
+impl Light {
+    // Light shining from the direction (x, y, z) with an RGB colour from 0 to 1
+    pub fn directional((x, y, z): (f32, f32, f32), color: [f32; 3]) -> Self {
+        let mut position = Vector3f { x, y, z };
+        position.norm();
+        Light {
+            kind: LightKind::Directional,
+            position,
+            color,
+        }
+    }
+
+    // Light shining from the position (x, y, z) with an RGB colour from 0 to 1
+    pub fn point((x, y, z): (f32, f32, f32), color: [f32; 3]) -> Self {
+        Light {
+            kind: LightKind::Point,
+            position: Vector3f { x, y, z },
+            color,
+        }
+    }
+}
+
+fn luma(rgb: [f32; 3]) -> f32 {
+    0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+}
+
+// Ambient, diffuse and specular (Phong) lighting. Characters can only show brightness, so a
+// coloured light brightens each face by how much of the face's colour it reflects
+pub struct Lighting {
+    lights: Vec<Light>,
+    rotated: Vec<Light>,
+    // Fraction of each light reflected by each face colour, indexed by [light][face]
+    tints: Vec<[f32; 6]>,
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub shininess: f32,
+    pub per_pixel: bool,
+    // Speeds "d,e,f" in radians per second at which the lights orbit the cube
+    pub orbit: (f32, f32, f32),
+    angles: (f32, f32, f32),
+}
+
+impl Lighting {
+    // Lights with matte reflection only, tinted by the face colours `faces`. Without lights the
+    // cube is lit from above and behind the viewer
+    pub fn new(lights: Vec<Light>, faces: &[&str; 6]) -> Self {
+        let lights = if lights.is_empty() {
+            vec![Light::directional(DEFAULT_LIGHT, WHITE)]
+        } else {
+            lights
+        };
+        let tints = lights
+            .iter()
+            .map(|light| {
+                Face::ALL.map(|face| {
+                    let rgb = color::to_rgb(faces[face as usize]).map(|c| c as f32 / 255.0);
+                    let lit = [0, 1, 2].map(|i| rgb[i] * light.color[i]);
+                    // Black reflects nothing of any light, so any tint leaves it dark
+                    match luma(rgb) {
+                        0.0 => 1.0,
+                        luma_rgb => luma(lit) / luma_rgb,
+                    }
+                })
+            })
+            .collect();
+
+        Lighting {
+            rotated: lights.clone(),
+            lights,
+            tints,
+            ambient: 0.0,
+            diffuse: 1.0,
+            specular: 0.0,
+            shininess: 16.0,
+            per_pixel: false,
+            orbit: (0.0, 0.0, 0.0),
+            angles: (0.0, 0.0, 0.0),
+        }
+    }
+
+    // Moves the lights along their orbit by `elapsed` seconds
+    pub fn orbit(&mut self, elapsed: f32) {
+        if self.orbit == (0.0, 0.0, 0.0) {
+            return;
+        }
+        self.angles.0 += self.orbit.0 * elapsed;
+        self.angles.1 += self.orbit.1 * elapsed;
+        self.angles.2 += self.orbit.2 * elapsed;
+
+        let (d, e, f) = self.angles;
+        let trig_values = [d.sin(), d.cos(), e.sin(), e.cos(), f.sin(), f.cos()];
+        for (rotated, light) in self.rotated.iter_mut().zip(&self.lights) {
+            rotated.position = light.position;
+            rotated.position.rot(&trig_values);
+        }
+    }
+
+    // Brightness from 0 to 1 of a point with unit surface normal `normal` at `position`,
+    // seen from the unit direction `view` pointing towards the camera
+    pub fn shade(
+        &self,
+        face: Face,
+        normal: &Vector3f,
+        position: &Vector3f,
+        view: &Vector3f,
+    ) -> f32 {
+        let mut luminance = self.ambient;
+
+        for (light, tints) in self.rotated.iter().zip(&self.tints) {
+            let mut to_light = match light.kind {
+                LightKind::Directional => light.position,
+                LightKind::Point => Vector3f {
+                    x: light.position.x - position.x,
+                    y: light.position.y - position.y,
+                    z: light.position.z - position.z,
+                },
+            };
+            to_light.norm();
+
+            let diffuse = normal.dot(&to_light);
+            if diffuse <= 0.0 {
+                continue;
+            }
+            luminance += self.diffuse * diffuse * tints[face as usize];
+
+            if self.specular > 0.0 {
+                // Light reflected about the normal
+                let reflected = Vector3f {
+                    x: 2.0 * diffuse * normal.x - to_light.x,
+                    y: 2.0 * diffuse * normal.y - to_light.y,
+                    z: 2.0 * diffuse * normal.z - to_light.z,
+                };
+                let specular = reflected.dot(view).max(0.0).powf(self.shininess);
+                luminance += self.specular * specular * luma(light.color);
+            }
+        }
+        luminance.min(1.0)
+    }
+}
@@ -1,331 +1,295 @@
 // Copyright (c) 2023 doprz
 // SPDX-License-Identifier: MIT OR Apache-2.0
-use clap::Parser;
-use libc::{ioctl, signal, winsize, SIGINT, STDOUT_FILENO, TIOCGWINSZ};
-use std::io::{self, Write};
-use std::sync::atomic::{AtomicBool, Ordering};
-pub mod ansi_escape_code;
-pub mod color;
-pub mod init;
-
-static SIGINT_CALLED: AtomicBool = AtomicBool::new(false);
-
-const CUBE_SIZE: f32 = 1.0;
-const GRID_LINE_COLOR: &str = color::BLACK;
-const K2: f32 = 10.0;
-
-enum Axes {
-    AFront,
-    ABack,
-    BFront,
-    BBack,
-    CFront,
-    CBack,
-}
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{Parser, Subcommand};
+use libc::{ioctl, winsize, STDOUT_FILENO, TIOCGWINSZ};
+use rs_cube::{ansi_escape_code, camera, color, cube, labels, light, theme};
+use rs_cube::{Renderer, Scene, Turn};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::atomic::Ordering;
+pub mod animation;
+pub mod asciicast;
+pub mod bench;
+pub mod config;
+pub mod font;
+pub mod gif;
+pub mod headless;
+pub mod input;
+pub mod keys;
+pub mod net;
+pub mod output;
+pub mod pacing;
+pub mod png;
+pub mod svg;
+pub mod terminal;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Config file to read instead of $XDG_CONFIG_HOME/rs-cube/config.toml
+    #[arg(long, value_name = "FILE", global = true)]
+    config: Option<std::path::PathBuf>,
+
     /// Enable debug info
     #[arg(short, long, default_value_t = false)]
     debug: bool,
 
     #[arg(short, long, default_value_t = 60u32)]
     fps_limit: u32,
-}
 
-#[derive(Clone, Copy)]
-struct Vector3f {
-    x: f32,
-    y: f32,
-    z: f32,
-}
+    /// Drop frames that can't be drawn in time, keeping the others on the frame limit's schedule
+    #[arg(long, default_value_t = false)]
+    frame_skip: bool,
 
-impl Vector3f {
-    fn mag(&self) -> f32 {
-        let x: f32 = self.x;
-        let y: f32 = self.y;
-        let z: f32 = self.z;
+    /// Starting cube state as 54 facelets in URFDLB order
+    #[arg(long, global = true)]
+    state: Option<String>,
 
-        (x * x + y * y + z * z).sqrt()
-    }
+    /// Moves applied to the starting state, e.g. "R U R' U'"
+    #[arg(long, global = true, visible_alias = "alg", allow_hyphen_values = true)]
+    scramble: Option<String>,
 
-    fn norm(&mut self) {
-        let x: f32 = self.x;
-        let y: f32 = self.y;
-        let z: f32 = self.z;
+    /// Show the unfolded cube net next to or below the 3D view, always below it in plain text
+    #[arg(long, value_enum)]
+    net: Option<net::Placement>,
 
-        let mag: f32 = self.mag();
-        let oomag: f32 = 1.0 / mag;
+    /// Moves played one after another in a loop, e.g. "R U R' U'"
+    #[arg(long, allow_hyphen_values = true)]
+    moves: Option<String>,
 
-        if mag > 0.0 {
-            self.x = x * oomag;
-            self.y = y * oomag;
-            self.z = z * oomag;
-        }
-    }
+    /// Time between played moves in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 500u64)]
+    move_interval: u64,
 
-    fn dot(&self, vec: &Vector3f) -> f32 {
-        self.x * vec.x + self.y * vec.y + self.z * vec.z
-    }
+    /// Duration of each layer turn in milliseconds, 0 to turn instantly
+    #[arg(long, value_name = "MS", default_value_t = 200u64)]
+    turn_time: u64,
 
-    fn rot(&mut self, trig_values: &[f32]) {
-        let trig_values = &trig_values[..6];
-        let (sin_a, sin_b, sin_c) = (&trig_values[0], &trig_values[2], &trig_values[4]);
-        let (cos_a, cos_b, cos_c) = (&trig_values[1], &trig_values[3], &trig_values[5]);
+    /// Key bindings to start from, on top of which the config file's [bindings] table applies
+    #[arg(long, value_enum, default_value_t = keys::Preset::Standard)]
+    keys: keys::Preset,
 
-        let x_arg1 = cos_a * sin_b * sin_c - sin_a * cos_c;
-        let x_arg2 = cos_a * sin_b * cos_c + sin_a * sin_c;
-        let y_arg1 = sin_a * sin_b * sin_c + cos_a * cos_c;
-        let y_arg2 = sin_a * sin_b * cos_c - cos_a * sin_c;
+    /// Print a single frame as plain text and exit
+    #[arg(long, default_value_t = false)]
+    once: bool,
 
-        let prev = self.clone();
+    /// Record the session to an asciicast v2 file
+    #[arg(long, value_name = "FILE")]
+    record: Option<std::path::PathBuf>,
 
-        self.x = cos_a * cos_b * prev.x + (x_arg1) * prev.y + (x_arg2) * prev.z;
-        self.y = sin_a * cos_b * prev.x + (y_arg1) * prev.y + (y_arg2) * prev.z;
-        self.z = -prev.x * sin_b + prev.y * cos_b * sin_c + prev.z * cos_b * cos_c;
-    }
-}
+    /// Supersampling factor, rendering N x N samples per character to smooth edges
+    #[arg(long, value_name = "N", global = true, default_value_t = 1u16, value_parser = parse_aa)]
+    aa: u16,
+
+    /// Draw the outline of the cube and its stickers with / \ | _ - following the edges
+    #[arg(long, global = true, default_value_t = false)]
+    edges: bool,
+
+    /// Colour theme: classic, stickerless, monochrome, high-contrast, japanese, protanopia, deuteranopia, tritanopia or a theme file
+    #[arg(long, value_name = "THEME", global = true, default_value = "classic", value_parser = theme::parse_theme)]
+    theme: theme::Theme,
+
+    /// Mark each sticker with the letter or a symbol of its colour's face
+    #[arg(long, global = true, value_parser = labels_parser())]
+    labels: Option<labels::Labels>,
 
-struct AxesLuminance {
-    a: (f32, f32),
-    b: (f32, f32),
-    c: (f32, f32),
+    #[command(flatten)]
+    camera: CameraArgs,
+
+    #[command(flatten)]
+    lighting: LightingArgs,
+
+    #[command(flatten)]
+    animation: animation::AnimationArgs,
 }
 
-fn update_buffers(
-    i: f32,
-    j: f32,
-    k: f32,
-    width: u16,
-    height: u16,
-    buffer: &mut [char],
-    zbuffer: &mut [f32],
-    cbuffer: &mut [&str],
-    trig_values: &[f32],
-    char_color: bool,
-    axes: Axes,
-    luminance: f32,
-) {
-    assert!(zbuffer.len() == buffer.len() && cbuffer.len() == buffer.len() && luminance <= 1.0);
-    let trig_values = &trig_values[..6];
-
-    let (sin_a, sin_b, sin_c) = (trig_values[0], trig_values[2], trig_values[4]);
-    let (cos_a, cos_b, cos_c) = (trig_values[1], trig_values[3], trig_values[5]);
-
-    let cos_a_sin_b = cos_a * sin_b;
-    let sin_a_sin_b = sin_a * sin_b;
-
-    let x: f32 = cos_a * cos_b * j
-        + (cos_a_sin_b * sin_c - sin_a * cos_c) * i
-        + (cos_a_sin_b * cos_c + sin_a * sin_c) * k;
-    let y: f32 = sin_a * cos_b * j
-        + (sin_a_sin_b * sin_c + cos_a * cos_c) * i
-        + (sin_a_sin_b * cos_c - cos_a * sin_c) * k;
-    let z: f32 = -j * sin_b + i * cos_b * sin_c + k * cos_b * cos_c + K2;
-
-    let ooz: f32 = 1.0 / z; // "One over z"
-
-    let k1: f32 = ((width as f32) * K2 * 3.0) / (8.0 * ((3.0_f32).sqrt() * CUBE_SIZE));
-    let xp: u32 = (((width as f32) / (2.0)) + (k1 * ooz * x)) as u32;
-    let yp: u32 = (((height as f32) / (2.0)) - (k1 * ooz * y)) as u32;
-
-    let index: usize = (xp + yp * width as u32).try_into().unwrap();
-    let index_limit: usize = buffer.len();
-    let luminance_index: usize = (luminance * 11.0) as usize;
-
-    // Luminance ranges from -1 to +1 for the dot product of the plane normal and light source normalized 3D unit vectors
-    // If the luminance > 0, then the plane is facing towards the light source
-    // else if luminance < 0, then the plane is facing away from the light source
-    // else if luminance = 0, then the plane and the light source are perpendicular
-    if index < index_limit && ooz > zbuffer[index] {
-        zbuffer[index] = ooz;
-        cbuffer[index] = if char_color {
-            match axes {
-                Axes::AFront => color::YELLOW,
-                Axes::ABack => color::WHITE,
-                Axes::BFront => color::GREEN,
-                Axes::BBack => color::BLUE,
-                Axes::CFront => color::BOLD_RED,
-                Axes::CBack => color::RED,
-            }
-        } else {
-            GRID_LINE_COLOR
-        };
-        buffer[index] =
-            ".,-~:;=!*#$@".as_bytes()[if luminance > 0.0 { luminance_index } else { 0 }] as char;
+fn parse_aa(s: &str) -> Result<u16, String> {
+    match s {
+        "1" | "2" | "4" => Ok(s.parse().unwrap()),
+        _ => Err("supersampling factor must be 1, 2 or 4".to_string()),
     }
 }
 
-fn get_axes_luminance(rotated_light_source: &Vector3f, trig_values: &[f32]) -> AxesLuminance {
-    // Axis A
-    let mut a_surface_normal_front = Vector3f {
-        x: 0.0,
-        y: 0.0,
-        z: CUBE_SIZE,
-    };
-    let mut a_surface_normal_back = Vector3f {
-        x: 0.0,
-        y: 0.0,
-        z: -CUBE_SIZE,
-    };
+fn labels_parser() -> impl TypedValueParser<Value = labels::Labels> {
+    PossibleValuesParser::new(["letters", "symbols"]).map(|s| match s.as_str() {
+        "letters" => labels::Labels::Letters,
+        _ => labels::Labels::Symbols,
+    })
+}
 
-    a_surface_normal_front.rot(trig_values);
-    a_surface_normal_back.rot(trig_values);
-    a_surface_normal_front.norm();
-    a_surface_normal_back.norm();
+#[derive(clap::Args)]
+pub struct CameraArgs {
+    /// Camera projection
+    #[arg(long, global = true, default_value = "perspective", value_parser = projection_parser())]
+    projection: camera::Projection,
 
-    // Axis B
-    let mut b_surface_normal_front = Vector3f {
-        x: 0.0,
-        y: CUBE_SIZE,
-        z: 0.0,
-    };
-    let mut b_surface_normal_back = Vector3f {
-        x: 0.0,
-        y: -CUBE_SIZE,
-        z: 0.0,
-    };
+    /// Field of view in degrees, from 10 to 120
+    #[arg(long, global = true, default_value_t = camera::DEFAULT_FOV, value_parser = parse_fov)]
+    fov: f32,
 
-    b_surface_normal_front.rot(trig_values);
-    b_surface_normal_back.rot(trig_values);
-    b_surface_normal_front.norm();
-    b_surface_normal_back.norm();
+    /// Distance from the camera to the centre of the cube, from 2 to 100
+    #[arg(long, global = true, default_value_t = camera::DEFAULT_DISTANCE, value_parser = parse_distance)]
+    distance: f32,
 
-    // Axis C
-    let mut c_surface_normal_front = Vector3f {
-        x: CUBE_SIZE,
-        y: 0.0,
-        z: 0.0,
-    };
-    let mut c_surface_normal_back = Vector3f {
-        x: -CUBE_SIZE,
-        y: 0.0,
-        z: 0.0,
-    };
+    /// Character cell height divided by its width, from 0.5 to 4 [default: 2 in the terminal]
+    #[arg(long, global = true, value_parser = parse_aspect)]
+    aspect: Option<f32>,
+}
 
-    c_surface_normal_front.rot(trig_values);
-    c_surface_normal_back.rot(trig_values);
-    c_surface_normal_front.norm();
-    c_surface_normal_back.norm();
-
-    AxesLuminance {
-        a: (
-            a_surface_normal_front.dot(rotated_light_source),
-            a_surface_normal_back.dot(rotated_light_source),
-        ),
-        b: (
-            b_surface_normal_front.dot(rotated_light_source),
-            b_surface_normal_back.dot(rotated_light_source),
-        ),
-        c: (
-            c_surface_normal_front.dot(rotated_light_source),
-            c_surface_normal_back.dot(rotated_light_source),
-        ),
+impl CameraArgs {
+    pub fn camera(&self, default_aspect: f32) -> camera::Camera {
+        camera::Camera::new(
+            self.projection,
+            self.fov,
+            self.distance,
+            self.aspect.unwrap_or(default_aspect),
+        )
     }
 }
 
-fn render_frame<'a>(
-    mut handle: impl Write,
-    width: u16,
-    height: u16,
-    points: &[init::Point3D],
-    points_color: &[bool],
-    points_axis_range: &init::PointsAxisRange,
-    buffer: &mut [char],
-    buffer_prev: &mut [char],
-    cbuffer: &mut [&'a str],
-    cbuffer_prev: &mut [&'a str],
-    zbuffer: &mut [f32],
-    trig_values: &[f32],
-    rotated_light_source: &Vector3f,
-) {
-    buffer_prev.copy_from_slice(buffer);
-    cbuffer_prev.copy_from_slice(cbuffer);
-
-    buffer.fill(' ');
-    cbuffer.fill(color::RESET);
-    zbuffer.fill(0.0);
-
-    let points_color = &points_color[..points.len()];
-    let axes_luminance = get_axes_luminance(rotated_light_source, trig_values);
-
-    for index in 0..points.len() {
-        let point = &points[index];
-        let color = points_color[index];
-        let (axes, luminance) = if index < points_axis_range.a {
-            if index & 1 == 0 {
-                (Axes::AFront, axes_luminance.a.0)
-            } else {
-                (Axes::ABack, axes_luminance.a.1)
-            }
-        } else if index < points_axis_range.b {
-            if index & 1 == 0 {
-                (Axes::BFront, axes_luminance.b.0)
-            } else {
-                (Axes::BBack, axes_luminance.b.1)
-            }
-        } else {
-            if index & 1 == 0 {
-                (Axes::CFront, axes_luminance.c.0)
-            } else {
-                (Axes::CBack, axes_luminance.c.1)
-            }
-        };
-        update_buffers(
-            point.x,
-            point.y,
-            point.z,
-            width,
-            height,
-            buffer,
-            zbuffer,
-            cbuffer,
-            trig_values,
-            color,
-            axes,
-            luminance,
-        );
+fn parse_within(s: &str, range: std::ops::RangeInclusive<f32>) -> Result<f32, String> {
+    let value: f32 = s.parse().map_err(|_| format!("invalid number \"{}\"", s))?;
+    if !range.contains(&value) {
+        return Err(format!(
+            "{} is not in {}..={}",
+            value,
+            range.start(),
+            range.end()
+        ));
     }
+    Ok(value)
+}
 
-    let l_cbuffer = &cbuffer[..buffer.len()];
-    let l_buffer_prev = &buffer_prev[..buffer.len()];
-    let l_cbuffer_prev = &cbuffer_prev[..buffer.len()];
-    let mut prev_set_color: &str = color::RESET;
+fn parse_fov(s: &str) -> Result<f32, String> {
+    parse_within(s, camera::FOV_RANGE)
+}
 
-    for index in 0..buffer.len() {
-        let val = buffer[index];
-        let color = l_cbuffer[index];
-        if (val == l_buffer_prev[index]) && (color == l_cbuffer_prev[index]) {
-            continue;
+fn parse_distance(s: &str) -> Result<f32, String> {
+    parse_within(s, camera::DISTANCE_RANGE)
+}
+
+fn parse_aspect(s: &str) -> Result<f32, String> {
+    parse_within(s, camera::ASPECT_RANGE)
+}
+
+fn projection_parser() -> impl TypedValueParser<Value = camera::Projection> {
+    PossibleValuesParser::new(["perspective", "orthographic", "isometric"]).map(|s| {
+        match s.as_str() {
+            "perspective" => camera::Projection::Perspective,
+            "orthographic" => camera::Projection::Orthographic,
+            _ => camera::Projection::Isometric,
         }
+    })
+}
 
-        let x: u16 = (index % width as usize).try_into().unwrap();
-        let y: u16 = (index / width as usize).try_into().unwrap();
+#[derive(clap::Args)]
+pub struct LightingArgs {
+    /// Light as "directional:X,Y,Z" or "point:X,Y,Z" with an optional ":RRGGBB" colour [default: directional:0,1,-1]
+    #[arg(long = "light", value_name = "LIGHT", global = true, value_parser = parse_light, allow_hyphen_values = true)]
+    lights: Vec<light::Light>,
 
-        // Move cursor, add color, and print char
-        if color == prev_set_color {
-            write!(
-                handle,
-                "{}{}",
-                ansi_escape_code::SetCursorPos(y + 1, x + 1),
-                val
-            )
-            .unwrap();
-        } else {
-            write!(
-                handle,
-                "{}{}{}",
-                ansi_escape_code::SetCursorPos(y + 1, x + 1),
-                color,
-                val
-            )
-            .unwrap();
-            prev_set_color = color;
+    /// Light reaching every surface regardless of its direction
+    #[arg(long, global = true, default_value_t = 0.0)]
+    ambient: f32,
+
+    /// Strength of the matte reflection
+    #[arg(long, global = true, default_value_t = 1.0)]
+    diffuse: f32,
+
+    /// Strength of the shiny highlights
+    #[arg(long, global = true, default_value_t = 0.0)]
+    specular: f32,
+
+    /// Sharpness of the highlights
+    #[arg(long, global = true, default_value_t = 16.0)]
+    shininess: f32,
+
+    /// Light every point separately instead of each face as a whole
+    #[arg(long, global = true, default_value_t = false)]
+    per_pixel: bool,
+
+    /// Speeds "d,e,f" in radians per second at which the lights orbit the cube
+    #[arg(long, global = true, value_parser = parse_angles, allow_hyphen_values = true)]
+    light_orbit: Option<(f32, f32, f32)>,
+}
+
+impl LightingArgs {
+    pub fn lighting(&self, faces: &[&str; 6]) -> light::Lighting {
+        let mut lighting = light::Lighting::new(self.lights.clone(), faces);
+        lighting.ambient = self.ambient;
+        lighting.diffuse = self.diffuse;
+        lighting.specular = self.specular;
+        lighting.shininess = self.shininess;
+        lighting.per_pixel = self.per_pixel;
+        lighting.orbit = self.light_orbit.unwrap_or((0.0, 0.0, 0.0));
+        lighting
+    }
+}
+
+pub fn parse_angles(s: &str) -> Result<(f32, f32, f32), String> {
+    let angles: Vec<f32> = s
+        .split(',')
+        .map(|v| v.trim().parse::<f32>().map_err(|e| e.to_string()))
+        .collect::<Result<_, _>>()?;
+    match angles[..] {
+        [a, b, c] => Ok((a, b, c)),
+        _ => Err(format!(
+            "expected three comma separated angles, got \"{}\"",
+            s
+        )),
+    }
+}
+
+// Parses "directional:X,Y,Z" or "point:X,Y,Z", optionally followed by ":RRGGBB"
+fn parse_light(s: &str) -> Result<light::Light, String> {
+    let mut parts = s.split(':');
+    let directional = match parts.next() {
+        Some("directional" | "dir") => true,
+        Some("point") => false,
+        _ => {
+            return Err(format!(
+                "expected \"directional\" or \"point\" light, got \"{}\"",
+                s
+            ))
+        }
+    };
+    let position = parse_angles(parts.next().unwrap_or_default())
+        .map_err(|_| format!("expected light position \"X,Y,Z\" in \"{}\"", s))?;
+    let color = match parts.next() {
+        Some(hex) => theme::parse_rgb(hex)?.map(|c| c as f32 / 255.0),
+        None => light::WHITE,
+    };
+    if parts.next().is_some() {
+        return Err(format!("unexpected \":\" in light \"{}\"", s));
+    }
+
+    match directional {
+        true if position == (0.0, 0.0, 0.0) => {
+            Err("directional light needs a non-zero direction".to_string())
         }
+        true => Ok(light::Light::directional(position, color)),
+        false => Ok(light::Light::point(position, color)),
     }
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Render the cube to a PNG image or an animated GIF without a terminal
+    Render(headless::RenderArgs),
+    /// Replay an asciicast v2 recording
+    Play(asciicast::PlayArgs),
+    /// Export an SVG or HTML diagram of the cube net or 3D view
+    Export(svg::ExportArgs),
+    /// Time rendering and encoding frames without a terminal
+    Bench(bench::BenchArgs),
+    /// Show the configuration read from the config file and the environment
+    Config(config::ConfigArgs),
+}
+
 fn get_term_size() -> Result<(u16, u16), &'static str> {
     let mut size = winsize {
         ws_row: 0,
@@ -344,199 +308,325 @@ fn get_term_size() -> Result<(u16, u16), &'static str> {
     }
 }
 
-fn handle_exit() {
-    print!("{}", ansi_escape_code::EraseScreen);
-    print!("{}", ansi_escape_code::DisableAltBuffer);
-    print!("{}", color::RESET);
-    print!("{}", ansi_escape_code::CursorVisible);
-}
-
-fn handle_sigint() {
-    SIGINT_CALLED.store(true, Ordering::Relaxed);
+fn parse_cube(args: &Args) -> Result<cube::Cube, String> {
+    let mut cube = match &args.state {
+        Some(state) => cube::Cube::from_facelets(state)?,
+        None => cube::Cube::solved(),
+    };
+    if let Some(scramble) = &args.scramble {
+        cube.apply_alg(&cube::parse_alg(scramble)?);
+    }
+    Ok(cube)
 }
 
 fn main() {
-    unsafe {
-        signal(SIGINT, handle_sigint as usize);
-    }
-
-    let args = Args::parse();
+    let config = config::load().unwrap_or_else(|err| err.exit());
+    let args = &config.args;
 
-    print!("{}", ansi_escape_code::EnableAltBuffer);
-    print!("{}", ansi_escape_code::EraseScreen);
-    print!("{}", ansi_escape_code::CursorInvisible);
-
-    let mut width: u16 = 100;
-    let mut height: u16 = 50;
+    let cube = match parse_cube(args) {
+        Ok(cube) => cube,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(2);
+        }
+    };
+    let moves = match args.moves.as_deref().map(cube::parse_alg).transpose() {
+        Ok(moves) => moves.unwrap_or_default(),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(2);
+        }
+    };
 
-    let term_size = get_term_size();
+    let animation = match animation::Animation::from_args(&args.animation) {
+        Ok(animation) => animation,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(2);
+        }
+    };
 
-    match term_size {
-        Ok(s) => {
-            width = s.0;
-            height = s.1;
+    if let Some(command) = &args.command {
+        let result = match command {
+            Command::Render(render_args) => headless::render(render_args, &cube, args),
+            Command::Play(play_args) => asciicast::play(play_args),
+            Command::Export(export_args) => svg::export(export_args, &cube, &args.camera),
+            Command::Bench(bench_args) => bench::bench(bench_args, &cube, &animation, args),
+            Command::Config(config_args) => config::run(config_args, &config),
+        };
+        if let Err(err) = result {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
         }
+        return;
+    }
+
+    let cast_file = match args.record.as_ref().map(std::fs::File::create).transpose() {
+        Ok(file) => file,
         Err(err) => {
-            println!("{}", err)
+            eprintln!(
+                "error: {}: {}",
+                args.record.as_ref().unwrap().display(),
+                err
+            );
+            std::process::exit(1);
         }
+    };
+
+    // Anything but an interactive terminal gets whole frames as plain text
+    let output = output::Output::detect();
+    let plain = args.once || !output.terminal;
+
+    let (mut width, mut height) = get_term_size().unwrap_or_else(|_| output::plain_size());
+    if !Renderer::fits(width, height, args.aa) {
+        <Args as clap::CommandFactory>::command()
+            .error(
+                clap::error::ErrorKind::ValueValidation,
+                format!(
+                    "--aa {} is too large for a {}x{} terminal",
+                    args.aa, width, height
+                ),
+            )
+            .exit();
     }
 
-    let stdout = io::stdout(); // get the global stdout entity
-                               // wrap that handle in a buffer and aquire a lock on it
-    let mut handle = io::BufWriter::with_capacity((width * height * 3).into(), stdout.lock());
+    terminal::install_signal_handlers();
+    // Erasing fills the screen with the current background
+    let background =
+        Some(args.theme.blank).filter(|_| output.color && args.theme.background.is_some());
+    let guard = (!plain).then(|| terminal::TerminalGuard::enter(background));
+
+    let mut handle = asciicast::Recorder::new(output::RawStdout, cast_file, width, height)
+        .expect("Error starting recording");
+    // Each frame is put together here and written at once. Terminals that know synchronized
+    // updates are also told where it starts and ends, so they never show it half drawn
+    let mut frame_buf: Vec<u8> = Vec::with_capacity(usize::from(width) * usize::from(height) * 3);
+    let synchronized = guard.is_some() && terminal::supports_synchronized_output();
+
+    // The net panel takes its space away from the 3D view
+    let net_pos = args
+        .net
+        .filter(|_| !plain)
+        .map(|placement| match placement {
+            net::Placement::Right => {
+                let x = width.saturating_sub(net::WIDTH);
+                width = x.saturating_sub(1).max(1);
+                (x, height.saturating_sub(net::HEIGHT) / 2)
+            }
+            net::Placement::Below => {
+                let y = height.saturating_sub(net::HEIGHT);
+                height = y.saturating_sub(1).max(1);
+                (width.saturating_sub(net::WIDTH) / 2, y)
+            }
+        });
+    let mut net_dirty = true;
+
+    let mut next_move: usize = 0;
+    let mut last_move = std::time::Instant::now();
+    let mut current_move: Option<cube::Move> = None;
+    let turn_time = args.turn_time.min(args.move_interval) as f32 / 1000.0;
+
+    let mut renderer = Renderer::new(width, height, args.aa, args.theme.clone());
+    renderer.edges = args.edges;
+    renderer.labels = args.labels;
+
+    let initial_camera = args.camera.camera(camera::DEFAULT_ASPECT);
+    let mut scene = Scene {
+        camera: initial_camera,
+        lighting: args.lighting.lighting(&args.theme.faces),
+        ..Scene::new(cube.clone())
+    };
 
-    let mut buffer: Vec<char> = vec![' '; (width * height).into()];
-    let mut buffer_prev: Vec<char> = vec![' '; (width * height).into()];
+    let bindings = keys::Bindings::new(args.keys, &config.bindings);
+    // Moves typed in, waiting for the turn before them to finish
+    let mut key_moves: VecDeque<cube::Move> = VecDeque::new();
+    let mut show_help = false;
 
-    let mut cbuffer: Vec<&str> = vec![color::RESET; (width * height).into()];
-    let mut cbuffer_prev: Vec<&str> = vec![color::RESET; (width * height).into()];
+    let total_frames = 10_000;
+    let mut frame_times: Vec<u128> = Vec::with_capacity(total_frames);
 
-    let mut zbuffer: Vec<f32> = vec![0.0; (width * height).into()];
+    // Seconds the cube has been turning for
+    let mut animation_time: f32 = 0.0;
 
-    assert!(
-        zbuffer.len() == buffer.len()
-            && cbuffer.len() == buffer.len()
-            && buffer_prev.len() == buffer.len()
-            && cbuffer_prev.len() == buffer.len()
-    );
+    // The animation follows the clock, so it runs at the same speed whatever the frame rate
+    let mut pacer = pacing::Pacer::new(args.fps_limit, args.frame_skip);
+    let mut last_frame = std::time::Instant::now();
 
-    let spacing: f32 = 3.0 / width as f32;
-    let _k1: f32 = ((width as f32) * K2 * 3.0) / (8.0 * (3_f32.sqrt() * CUBE_SIZE));
+    while !terminal::exit_requested() {
+        let start = std::time::Instant::now();
 
-    let points_size = ((CUBE_SIZE * CUBE_SIZE) / spacing).round() as usize;
-    let mut points: Vec<init::Point3D> = Vec::with_capacity(points_size);
-    let mut points_color: Vec<bool> = Vec::with_capacity(points_size);
-    let mut points_axis_range = init::PointsAxisRange { a: 0, b: 0, c: 0 };
+        // Everything is drawn again after coming back from ^Z, carrying on where it stopped
+        if guard
+            .as_ref()
+            .is_some_and(|guard| guard.suspend_if_requested())
+        {
+            renderer.invalidate();
+            net_dirty = true;
+            pacer.reset();
+            last_frame = std::time::Instant::now();
+        }
+        let elapsed = last_frame.elapsed().as_secs_f32();
+        last_frame = std::time::Instant::now();
+
+        for key in input::read_keys() {
+            match bindings.action(key) {
+                Some(keys::Action::Move(m)) => key_moves.push_back(m),
+                Some(keys::Action::Camera(action)) => scene.camera.apply(action),
+                Some(keys::Action::ResetCamera) => scene.camera = initial_camera,
+                Some(keys::Action::ResetCube) => {
+                    key_moves.clear();
+                    current_move = None;
+                    next_move = 0;
+                    scene.cube = cube.clone();
+                    net_dirty = true;
+                }
+                // The overlay is drawn over the frame, which has to be drawn whole once it goes
+                Some(keys::Action::Help) => {
+                    show_help = !show_help;
+                    if !show_help {
+                        renderer.invalidate();
+                        net_dirty = true;
+                    }
+                }
+                Some(keys::Action::Quit) => terminal::EXIT_REQUESTED.store(true, Ordering::Relaxed),
+                None => {}
+            }
+        }
 
-    let light_source = Vector3f {
-        x: 0.0,
-        y: 1.0,
-        z: -1.0,
-    };
-    let mut rotated_light_source = Vector3f {
-        x: 0.0,
-        y: 1.0,
-        z: -1.0,
-    };
+        scene.lighting.orbit(elapsed);
 
-    let total_frames = 10_000;
-    let mut frame_times: Vec<u128> = Vec::with_capacity(total_frames);
+        // Fixed camera angles pause the rotation
+        scene.angles = match scene.camera.fixed_angles() {
+            Some(angles) => angles,
+            None => {
+                animation_time += elapsed;
+                animation.angles(animation_time)
+            }
+        };
 
-    let mut a: f32 = -std::f32::consts::FRAC_PI_2; // Axis facing the screen (z-axis)
-    let mut b: f32 = -std::f32::consts::FRAC_PI_2; // Up / Down axis (y-axis)
-    let mut c: f32 = std::f32::consts::FRAC_PI_2 + std::f32::consts::FRAC_PI_4; // Left / Right axis (x-axis)
-
-    // Rotated Light Source
-    let d: f32 = 0.0;
-    let e: f32 = 0.0;
-    let f: f32 = 0.0;
-
-    let sin_d: f32 = d.sin();
-    let cos_d: f32 = d.cos();
-
-    let sin_e: f32 = e.sin();
-    let cos_e: f32 = e.cos();
-
-    let sin_f: f32 = f.sin();
-    let cos_f: f32 = f.cos();
-
-    rotated_light_source.x = cos_d * cos_e * light_source.x
-        + (cos_d * sin_e * sin_f - sin_d * cos_f) * light_source.y
-        + (cos_d * sin_e * cos_f + sin_d * sin_f) * light_source.z;
-    rotated_light_source.y = sin_d * cos_e * light_source.x
-        + (sin_d * sin_e * sin_f + cos_d * cos_f) * light_source.y
-        + (sin_d * sin_e * cos_f - cos_d * sin_f) * light_source.z;
-    rotated_light_source.z =
-        -light_source.x * sin_e + light_source.y * cos_e * sin_f + light_source.z * cos_e * cos_f;
-    rotated_light_source.norm();
-
-    init::init(
-        &mut points,
-        &mut points_color,
-        &mut points_axis_range,
-        spacing,
-    );
-
-    while !SIGINT_CALLED.load(Ordering::Relaxed) {
-        let start = std::time::Instant::now();
-        a += 0.03;
-        b += 0.02;
-        c += 0.01;
-
-        let sin_a: f32 = a.sin();
-        let cos_a: f32 = a.cos();
-
-        let sin_b: f32 = b.sin();
-        let cos_b: f32 = b.cos();
-
-        let sin_c: f32 = c.sin();
-        let cos_c: f32 = c.cos();
-        let trig_values: Vec<f32> = vec![sin_a, cos_a, sin_b, cos_b, sin_c, cos_c];
-
-        assert!(
-            zbuffer.len() == buffer.len()
-                && cbuffer.len() == buffer.len()
-                && buffer_prev.len() == buffer.len()
-                && cbuffer_prev.len() == buffer.len()
-        );
-        render_frame(
-            &mut handle,
-            width,
-            height,
-            &points,
-            &points_color,
-            &points_axis_range,
-            &mut buffer,
-            &mut buffer_prev,
-            &mut cbuffer,
-            &mut cbuffer_prev,
-            &mut zbuffer,
-            &trig_values,
-            &rotated_light_source,
-        );
+        // Typed moves go before the played ones
+        if current_move.is_none() {
+            if let Some(m) = key_moves.pop_front() {
+                current_move = Some(m);
+                last_move = std::time::Instant::now();
+            } else if !moves.is_empty()
+                && last_move.elapsed().as_millis() >= args.move_interval.into()
+            {
+                current_move = Some(moves[next_move]);
+                next_move = (next_move + 1) % moves.len();
+                last_move = std::time::Instant::now();
+            }
+        }
 
-        {
-            let us_duration = start.elapsed().as_micros();
-            let frame_duration_micro: u32 = 1_000_000
-                / (if args.fps_limit != 0 {
-                    args.fps_limit
-                } else {
-                    1
+        // Moves turn their layers smoothly and are applied to the facelets once the turn ends
+        scene.turn = None;
+        if let Some(m) = current_move {
+            let progress = last_move.elapsed().as_secs_f32() / turn_time;
+            if progress >= 1.0 || turn_time == 0.0 {
+                scene.cube.apply(&m);
+                current_move = None;
+                net_dirty = true;
+            } else {
+                let (axis, layers, quarter_turns) = m.spec();
+                let eased = progress * progress * (3.0 - 2.0 * progress);
+                let angle = quarter_turns as f32 * std::f32::consts::FRAC_PI_2 * eased;
+                scene.turn = Some(Turn {
+                    axis,
+                    layers,
+                    angle,
                 });
-            if args.fps_limit != 0 && us_duration < frame_duration_micro.into() {
-                let diff = frame_duration_micro as u128 - us_duration;
-                let sleep_dur = std::time::Duration::from_micros(diff.try_into().unwrap());
-                std::thread::sleep(sleep_dur);
             }
         }
 
-        if args.debug {
+        let frame = renderer.render(&scene);
+
+        frame_buf.clear();
+        if synchronized {
+            write!(frame_buf, "{}", ansi_escape_code::BeginSynchronizedUpdate).unwrap();
+        }
+
+        // Plain frames are separated by an empty line
+        let written = if plain {
+            frame
+                .write_plain(&mut frame_buf, output.color)
+                .and_then(|_| match args.net {
+                    // Plain frames have the net below them wherever it is placed
+                    Some(_) => net::write_plain(
+                        &mut frame_buf,
+                        &scene.cube,
+                        &args.theme,
+                        args.labels,
+                        output.color,
+                    ),
+                    None => Ok(()),
+                })
+                .and_then(|_| {
+                    if args.once {
+                        Ok(())
+                    } else {
+                        writeln!(frame_buf)
+                    }
+                })
+        } else {
+            frame.write_changes(&mut frame_buf, output.color)
+        };
+
+        if let Some((x, y)) = net_pos.filter(|_| net_dirty) {
+            net::draw(
+                &mut frame_buf,
+                &scene.cube,
+                &args.theme,
+                args.labels,
+                output.color,
+                x,
+                y,
+            )
+            .unwrap();
+            net_dirty = false;
+        }
+
+        if show_help {
+            bindings
+                .draw_help(&mut frame_buf, &args.theme, output.color, width, height)
+                .unwrap();
+        }
+
+        if !args.once {
+            pacer.wait();
+        }
+
+        if args.debug && !plain {
             let us_duration = start.elapsed().as_micros();
             let ms_duration = us_duration as f64 / 1000.0;
             let fps: f64 = 1_000_000.0 / (us_duration as f64);
 
             frame_times.push(us_duration);
+            let blank = if output.color {
+                args.theme.blank
+            } else {
+                color::RESET
+            };
 
             write!(
-                handle,
+                frame_buf,
                 "{}{}{}\r",
                 ansi_escape_code::SetCursorPos(1, 1 + 11),
-                color::RESET,
+                blank,
                 ansi_escape_code::EraseLineStartToCursor
             )
             .unwrap();
-            write!(handle, "{fps:>8.2}fps", fps = fps).unwrap();
+            write!(frame_buf, "{fps:>8.2}fps", fps = fps).unwrap();
 
             write!(
-                handle,
+                frame_buf,
                 "{}{}{}\r",
                 ansi_escape_code::SetCursorPos(2, 1 + 22),
-                color::RESET,
+                blank,
                 ansi_escape_code::EraseLineStartToCursor
             )
             .unwrap();
             write!(
-                handle,
+                frame_buf,
                 "{ms:>8.2}ms ({us:>7}us)",
                 ms = ms_duration,
                 us = us_duration
@@ -544,30 +634,35 @@ fn main() {
             .unwrap();
         }
 
-        handle.flush().expect("Error flushing handle");
+        if synchronized {
+            write!(frame_buf, "{}", ansi_escape_code::EndSynchronizedUpdate).unwrap();
+        }
+
+        // Stop quietly once whatever reads the frames goes away
+        let written = written
+            .and_then(|_| handle.write_all(&frame_buf))
+            .and_then(|_| handle.flush());
+        if written.is_err() || args.once {
+            break;
+        }
     }
 
-    handle_exit();
+    drop(guard);
 
     if args.debug {
         println!("Width: {} | Height: {}", width, height);
 
         let sum: u128 = frame_times.iter().sum();
         let frames = frame_times.len();
-        let frame_avg = if frames == 0 {
-            sum
-        } else {
-            sum / frames as u128
-        };
-        let fps_avg = if frame_avg == 0 {
-            0
-        } else {
-            1_000_000 / frame_avg
-        };
+        let frame_avg = sum.checked_div(frames as u128).unwrap_or(sum);
+        let fps_avg = 1_000_000_u128.checked_div(frame_avg).unwrap_or(0);
 
         println!("Frame Average: {}us", frame_avg);
         println!("FPS Average: {}", fps_avg);
+        if args.frame_skip {
+            println!("Frames Skipped: {}", pacer.skipped);
+        }
 
-        println!("Points: {}", points.len());
+        println!("Points: {}", renderer.points());
     }
 }
@@ -0,0 +1,129 @@
+use rs_cube::cube::{Cube, Face};
+use rs_cube::labels::Labels;
+use rs_cube::theme::Theme;
+use rs_cube::{ansi_escape_code, color};
+use std::io::{self, Write};
+
+// Each face is 3 stickers of 2 columns by 3 rows, separated by one blank column / row
+pub const WIDTH: u16 = 4 * 7 - 1;
+pub const HEIGHT: u16 = 3 * 4 - 1;
+const STICKER: &str = "██";
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Placement {
+    Right,
+    Below,
+}
+
+// Position of a face in the net in units of faces: U above, L F R B in a row, D below
+fn face_position(face: Face) -> (u16, u16) {
+    match face {
+        Face::U => (1, 0),
+        Face::L => (0, 1),
+        Face::F => (1, 1),
+        Face::R => (2, 1),
+        Face::B => (3, 1),
+        Face::D => (1, 2),
+    }
+}
+
+// Draws the net with its top left corner at the zero-based cell (x, y). Without colour every
+// sticker is labelled with its face letter
+pub fn draw(
+    mut handle: impl Write,
+    cube: &Cube,
+    theme: &Theme,
+    labels: Option<Labels>,
+    use_color: bool,
+    x: u16,
+    y: u16,
+) -> io::Result<()> {
+    let labels = match use_color {
+        true => labels,
+        false => labels.or(Some(Labels::Letters)),
+    };
+    for face in Face::ALL {
+        let (col, row) = face_position(face);
+        for r in 0..3 {
+            write!(
+                handle,
+                "{}",
+                ansi_escape_code::SetCursorPos(y + row * 4 + r + 1, x + col * 7 + 1)
+            )?;
+            write_stickers(&mut handle, cube, theme, labels, use_color, face, r)?;
+        }
+    }
+    match use_color {
+        true => write!(handle, "{}", theme.blank),
+        false => Ok(()),
+    }
+}
+
+// Writes the net as lines of text, for output that isn't an interactive terminal. Without
+// colour the stickers are face letters alone, with no escape codes
+pub fn write_plain(
+    mut handle: impl Write,
+    cube: &Cube,
+    theme: &Theme,
+    labels: Option<Labels>,
+    use_color: bool,
+) -> io::Result<()> {
+    for line in 0..HEIGHT {
+        let (row, r) = (line / 4, line % 4);
+        let mut faces: Vec<Face> = Face::ALL
+            .into_iter()
+            .filter(|&face| r < 3 && face_position(face).1 == row)
+            .collect();
+        faces.sort_by_key(|&face| face_position(face).0);
+
+        let mut pos = 0;
+        for face in faces {
+            let start = face_position(face).0 * 7;
+            write!(handle, "{:1$}", "", usize::from(start - pos))?;
+            if use_color {
+                write_stickers(&mut handle, cube, theme, labels, use_color, face, r)?;
+                write!(handle, "{}", color::RESET)?;
+                pos = start + 6;
+            } else {
+                for c in 0..3 {
+                    let facelet = cube.facelets[face as usize * 9 + (r * 3 + c) as usize];
+                    let glyph = labels.unwrap_or(Labels::Letters).glyph(facelet);
+                    write!(handle, "{}{}", glyph, if c < 2 { " " } else { "" })?;
+                }
+                pos = start + 5;
+            }
+        }
+        writeln!(handle)?;
+    }
+    Ok(())
+}
+
+// The three stickers of a row of a face
+fn write_stickers(
+    mut handle: impl Write,
+    cube: &Cube,
+    theme: &Theme,
+    labels: Option<Labels>,
+    use_color: bool,
+    face: Face,
+    r: u16,
+) -> io::Result<()> {
+    for c in 0..3 {
+        let facelet = cube.facelets[face as usize * 9 + (r * 3 + c) as usize];
+        if use_color {
+            write!(handle, "{}", theme.faces[facelet as usize])?;
+        }
+        match labels {
+            // Labels are cut out of the sticker in the background colour
+            Some(labels) => write!(
+                handle,
+                "{}{} {}",
+                ansi_escape_code::ReverseVideo,
+                labels.glyph(facelet),
+                ansi_escape_code::NoReverseVideo
+            )?,
+            None => write!(handle, "{}", STICKER)?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use libc::{isatty, write, STDOUT_FILENO};
+use std::io;
+
+// Size used for plain frames when COLUMNS and LINES are not set
+const PLAIN_SIZE: (u16, u16) = (80, 24);
+
+// Where frames go: an interactive terminal is redrawn in place, anything else gets whole
+// frames as plain lines of text
+#[derive(Clone, Copy)]
+pub struct Output {
+    pub terminal: bool,
+    pub color: bool,
+}
+
+impl Output {
+    pub fn detect() -> Self {
+        let terminal = unsafe { isatty(STDOUT_FILENO) } == 1;
+        // https://no-color.org: any non-empty value turns colour off
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Output {
+            terminal,
+            color: terminal && !no_color,
+        }
+    }
+}
+
+// Size for plain frames, from the COLUMNS and LINES variables shells set
+pub fn plain_size() -> (u16, u16) {
+    let var = |name: &str| {
+        std::env::var(name)
+            .ok()?
+            .parse::<u16>()
+            .ok()
+            .filter(|&n| n > 0)
+    };
+    (
+        var("COLUMNS").unwrap_or(PLAIN_SIZE.0),
+        var("LINES").unwrap_or(PLAIN_SIZE.1),
+    )
+}
+
+// Unbuffered standard output, so a frame put together in memory reaches the terminal in one
+// write instead of being split at line breaks the way `io::Stdout` does
+pub struct RawStdout;
+
+impl io::Write for RawStdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = unsafe { write(STDOUT_FILENO, buf.as_ptr().cast(), buf.len()) };
+        match n {
+            -1 => Err(io::Error::last_os_error()),
+            n => Ok(n as usize),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use libc::{clock_gettime, timespec, CLOCK_MONOTONIC};
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+// Holds frames to a steady rate by sleeping until absolute deadlines on the monotonic clock,
+// so the time spent drawing and any oversleeping never add up to drift
+pub struct Pacer {
+    // Nanoseconds per frame, 0 without a limit
+    period: u64,
+    // End of the current frame's slot
+    deadline: u64,
+    // Drop the frames that can't make it in time instead of starting the schedule again
+    skip: bool,
+    pub skipped: u64,
+}
+
+impl Pacer {
+    pub fn new(fps: u32, skip: bool) -> Self {
+        let period = NANOS_PER_SEC.checked_div(fps.into()).unwrap_or(0);
+        Pacer {
+            period,
+            deadline: now() + period,
+            skip,
+            skipped: 0,
+        }
+    }
+
+    // Starts the schedule over from now, after a pause that should not count as falling behind
+    pub fn reset(&mut self) {
+        self.deadline = now() + self.period;
+    }
+
+    // Waits for the end of the current frame's slot. A frame that ran over either starts the
+    // schedule again from now, or with frame skipping gives up the slots it missed so the
+    // frames keep to the schedule
+    pub fn wait(&mut self) {
+        if self.period == 0 {
+            return;
+        }
+        let now = now();
+        if now >= self.deadline {
+            if !self.skip {
+                self.deadline = now + self.period;
+                return;
+            }
+            let missed = (now - self.deadline) / self.period + 1;
+            self.skipped += missed;
+            self.deadline += missed * self.period;
+        }
+        sleep_until(self.deadline);
+        self.deadline += self.period;
+    }
+}
+
+// Nanoseconds on the monotonic clock
+fn now() -> u64 {
+    let mut ts = timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { clock_gettime(CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as u64 * NANOS_PER_SEC + ts.tv_nsec as u64
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn sleep_until(deadline: u64) {
+    let ts = timespec {
+        tv_sec: (deadline / NANOS_PER_SEC) as _,
+        tv_nsec: (deadline % NANOS_PER_SEC) as _,
+    };
+    // Caught signals end the sleep early
+    while unsafe {
+        libc::clock_nanosleep(
+            CLOCK_MONOTONIC,
+            libc::TIMER_ABSTIME,
+            &ts,
+            std::ptr::null_mut(),
+        )
+    } == libc::EINTR
+    {}
+}
+
+// Without absolute sleeps the remaining time is slept, which still keeps to the deadlines
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn sleep_until(deadline: u64) {
+    std::thread::sleep(std::time::Duration::from_nanos(
+        deadline.saturating_sub(now()),
+    ));
+}
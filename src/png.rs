@@ -0,0 +1,347 @@
+use std::io::{self, Write};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+
+// Writes an 8-bit RGB image, `pixels` being rows of packed RGB triplets
+pub fn write_rgb(mut w: impl Write, width: u32, height: u32, pixels: &[u8]) -> io::Result<()> {
+    assert!(pixels.len() == width as usize * height as usize * 3);
+
+    w.write_all(&SIGNATURE)?;
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]); // bit depth, colour type RGB, compression, filter, interlace
+    write_chunk(&mut w, b"IHDR", &ihdr)?;
+
+    // Every scanline is prefixed with filter type 0 (None)
+    let stride = width as usize * 3;
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+    for row in pixels.chunks(stride) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    write_chunk(&mut w, b"IDAT", &zlib_compress(&raw))?;
+    write_chunk(&mut w, b"IEND", &[])
+}
+
+fn write_chunk(w: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+
+    let crc = !crc32_update(crc32_update(!0, kind), data);
+    w.write_all(&crc.to_be_bytes())
+}
+
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    nbits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, nbits: u32) {
+        self.acc |= value << self.nbits;
+        self.nbits += nbits;
+        while self.nbits >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.nbits -= 8;
+        }
+    }
+
+    // Huffman codes are packed starting from their most significant bit
+    fn write_code(&mut self, code: u32, nbits: u32) {
+        self.write(code.reverse_bits() >> (32 - nbits), nbits);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.nbits > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
+
+fn write_literal(bits: &mut BitWriter, lit: u16) {
+    match lit {
+        0..=143 => bits.write_code(0x30 + lit as u32, 8),
+        144..=255 => bits.write_code(0x190 + (lit - 144) as u32, 9),
+        256..=279 => bits.write_code((lit - 256) as u32, 7),
+        _ => bits.write_code(0xC0 + (lit - 280) as u32, 8),
+    }
+}
+
+fn write_match(bits: &mut BitWriter, length: usize, dist: usize) {
+    let l = LENGTH_BASE
+        .iter()
+        .rposition(|&base| base as usize <= length)
+        .unwrap();
+    write_literal(bits, 257 + l as u16);
+    bits.write(
+        (length - LENGTH_BASE[l] as usize) as u32,
+        LENGTH_EXTRA[l] as u32,
+    );
+
+    let d = DIST_BASE
+        .iter()
+        .rposition(|&base| base as usize <= dist)
+        .unwrap();
+    bits.write_code(d as u32, 5);
+    bits.write((dist - DIST_BASE[d] as usize) as u32, DIST_EXTRA[d] as u32);
+}
+
+// Single-block deflate with the fixed Huffman tables and a one-candidate LZ77 hash
+fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut bits = BitWriter {
+        out: vec![0x78, 0x01],
+        acc: 0,
+        nbits: 0,
+    };
+    bits.write(1, 1); // BFINAL
+    bits.write(1, 2); // BTYPE = fixed Huffman
+
+    let hash = |i: usize| -> usize {
+        let v = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+        (v.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+    };
+    let mut head: Vec<usize> = vec![usize::MAX; 1 << HASH_BITS];
+
+    let mut i = 0;
+    while i < data.len() {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(i);
+            let candidate = head[h];
+            head[h] = i;
+
+            if candidate != usize::MAX && i - candidate <= WINDOW_SIZE {
+                let max = (data.len() - i).min(MAX_MATCH);
+                let length = (0..max)
+                    .take_while(|&n| data[candidate + n] == data[i + n])
+                    .count();
+
+                if length >= MIN_MATCH {
+                    write_match(&mut bits, length, i - candidate);
+                    for j in (i + 1)..(i + length).min(data.len() - MIN_MATCH + 1) {
+                        head[hash(j)] = j;
+                    }
+                    i += length;
+                    continue;
+                }
+            }
+        }
+        write_literal(&mut bits, data[i] as u16);
+        i += 1;
+    }
+    write_literal(&mut bits, 256); // End of block
+
+    let mut out = bits.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reads the bits of a fixed Huffman deflate stream
+    struct BitReader<'a> {
+        data: &'a [u8],
+        pos: usize,
+    }
+
+    impl BitReader<'_> {
+        fn bit(&mut self) -> u32 {
+            let bit = (self.data[self.pos / 8] >> (self.pos % 8)) & 1;
+            self.pos += 1;
+            bit as u32
+        }
+
+        fn bits(&mut self, n: u32) -> u32 {
+            (0..n).fold(0, |value, i| value | self.bit() << i)
+        }
+
+        // Huffman codes start from their most significant bit
+        fn code(&mut self, n: u32) -> u32 {
+            (0..n).fold(0, |code, _| code << 1 | self.bit())
+        }
+
+        fn symbol(&mut self) -> u16 {
+            let code = self.code(7);
+            if code <= 0x17 {
+                return 256 + code as u16;
+            }
+            let code = code << 1 | self.bit();
+            match code {
+                0x30..=0xBF => (code - 0x30) as u16,
+                0xC0..=0xC7 => (280 + code - 0xC0) as u16,
+                _ => (144 + (code << 1 | self.bit()) - 0x190) as u16,
+            }
+        }
+    }
+
+    fn zlib_decompress(data: &[u8]) -> Vec<u8> {
+        assert_eq!((data[0] as u16 * 256 + data[1] as u16) % 31, 0);
+        assert_eq!(data[0] & 0x0F, 8, "compression method must be deflate");
+        let mut bits = BitReader {
+            data: &data[2..data.len() - 4],
+            pos: 0,
+        };
+        assert_eq!(bits.bits(1), 1, "only a single final block is written");
+        assert_eq!(bits.bits(2), 1, "block must use the fixed Huffman tables");
+
+        let mut out: Vec<u8> = Vec::new();
+        loop {
+            let symbol = bits.symbol();
+            match symbol {
+                0..=255 => out.push(symbol as u8),
+                256 => break,
+                _ => {
+                    let l = (symbol - 257) as usize;
+                    let length =
+                        LENGTH_BASE[l] as usize + bits.bits(LENGTH_EXTRA[l] as u32) as usize;
+                    let d = bits.code(5) as usize;
+                    let dist = DIST_BASE[d] as usize + bits.bits(DIST_EXTRA[d] as u32) as usize;
+                    assert!(dist <= out.len() && dist <= WINDOW_SIZE);
+                    for _ in 0..length {
+                        out.push(out[out.len() - dist]);
+                    }
+                }
+            }
+        }
+        let checksum = u32::from_be_bytes(data[data.len() - 4..].try_into().unwrap());
+        assert_eq!(checksum, adler32(&out));
+        out
+    }
+
+    // Bytes that don't repeat for a long while, from a linear congruential generator
+    fn noise(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(!crc32_update(!0, b"123456789"), 0xCBF4_3926);
+        assert_eq!(!crc32_update(!0, b""), 0);
+        // CRC of an IEND chunk as every PNG ends with it
+        assert_eq!(!crc32_update(!0, b"IEND"), 0xAE42_6082);
+    }
+
+    #[test]
+    fn adler32_check_value() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        // Long enough for both sums to wrap around the modulus many times
+        assert_eq!(adler32(&[0xFF; 100_000]), 0x149A_302C);
+    }
+
+    #[test]
+    fn deflate_round_trip() {
+        let mut repeated_far = noise(40_000, 7);
+        repeated_far.extend_from_within(100..20_000);
+        let inputs = [
+            Vec::new(),
+            b"a".to_vec(),
+            b"abcabcabcabcabcabc".to_vec(),
+            vec![0; 10_000],
+            noise(5_000, 1),
+            (0..=255).cycle().take(70_000).collect(),
+            repeated_far,
+        ];
+        for input in inputs {
+            assert_eq!(zlib_decompress(&zlib_compress(&input)), input);
+        }
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let (width, height) = (37u32, 11u32);
+        let mut pixels = noise((width * height * 3) as usize, 3);
+        pixels[..60].fill(0x80);
+
+        let mut png = Vec::new();
+        write_rgb(&mut png, width, height, &pixels).unwrap();
+        assert_eq!(png[..8], SIGNATURE);
+
+        let mut rest = &png[8..];
+        let mut chunks = Vec::new();
+        let mut idat = Vec::new();
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + len]);
+            let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+            assert_eq!(crc, !crc32_update(crc32_update(!0, kind), data));
+            if kind == b"IDAT" {
+                idat.extend_from_slice(data);
+            }
+            chunks.push(String::from_utf8(kind.to_vec()).unwrap());
+            rest = &rest[12 + len..];
+        }
+        assert_eq!(chunks, ["IHDR", "IDAT", "IEND"]);
+
+        let raw = zlib_decompress(&idat);
+        let stride = width as usize * 3;
+        assert_eq!(raw.len(), (stride + 1) * height as usize);
+        let mut decoded = Vec::new();
+        for row in raw.chunks(stride + 1) {
+            assert_eq!(row[0], 0, "rows are not filtered");
+            decoded.extend_from_slice(&row[1..]);
+        }
+        assert_eq!(decoded, pixels);
+    }
+}
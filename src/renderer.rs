@@ -0,0 +1,445 @@
+use crate::camera::Camera;
+use crate::cube::{self, Cube};
+use crate::labels::{self, Labels};
+use crate::light::Lighting;
+use crate::supersample::Samples;
+use crate::theme::Theme;
+use crate::transform::{Transform, Transformed};
+use crate::{color, edges, encoder, init, rotate, Vector3f, CUBE_SIZE, START_ANGLES};
+use std::io::{self, Write};
+use std::ops::Range;
+
+/// A layer turn in progress, with the axis and layers of `cube::Move::spec`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Turn {
+    /// Axis the layers turn around, 0 to 2 for x, y and z
+    pub axis: usize,
+    /// Layers that turn, bit 0 for -1, bit 1 for 0 and bit 2 for +1 along the axis
+    pub layers: u8,
+    /// Angle turned so far in radians, counter-clockwise around the positive axis
+    pub angle: f32,
+}
+
+// Points transformed together, few enough for their results to stay in the cache
+const BLOCK: usize = 256;
+
+/// Everything that decides what a frame shows
+pub struct Scene {
+    pub cube: Cube,
+    /// Rotation "a,b,c" of the cube in radians
+    pub angles: (f32, f32, f32),
+    /// Layer turn drawn on top of the facelets of `cube`
+    pub turn: Option<Turn>,
+    pub camera: Camera,
+    pub lighting: Lighting,
+}
+
+impl Scene {
+    /// The cube at its starting angles, seen by the default camera under the default light
+    pub fn new(cube: Cube) -> Self {
+        Scene {
+            cube,
+            angles: START_ANGLES,
+            turn: None,
+            camera: Camera::default(),
+            lighting: Lighting::new(Vec::new(), &Theme::default().faces),
+        }
+    }
+}
+
+/// Draws scenes into a grid of characters, keeping the previous frame so only changes have
+/// to be written out
+pub struct Renderer {
+    width: u16,
+    height: u16,
+    buffer: Vec<char>,
+    buffer_prev: Vec<char>,
+    cbuffer: Vec<&'static str>,
+    cbuffer_prev: Vec<&'static str>,
+    samples: Samples,
+    // Samples drawn by each thread, merged into `samples`
+    #[cfg(feature = "parallel")]
+    partial_samples: Vec<Samples>,
+    model: init::Model,
+    // Spacing the model was sampled with, sampled again when the camera zooms
+    spacing: f32,
+    pub theme: Theme,
+    /// Outline the cube and its stickers with / \ | _ -
+    pub edges: bool,
+    /// Face letters or symbols drawn on the stickers
+    pub labels: Option<Labels>,
+}
+
+/// Characters of a rendered frame and their colours, row by row
+pub struct Frame<'a> {
+    pub width: u16,
+    pub height: u16,
+    pub chars: &'a [char],
+    pub colors: &'a [&'static str],
+    pub(crate) prev_chars: &'a [char],
+    pub(crate) prev_colors: &'a [&'static str],
+}
+
+impl Renderer {
+    /// Renderer for frames of `width` x `height` characters. `aa` is the supersampling
+    /// factor, rendering aa x aa samples per character
+    ///
+    /// Panics if the samples don't fit, which `Renderer::fits` checks beforehand
+    pub fn new(width: u16, height: u16, aa: u16, theme: Theme) -> Self {
+        assert!(
+            Renderer::fits(width, height, aa),
+            "{}x{} characters are too many for {}x{} samples each",
+            width,
+            height,
+            aa,
+            aa
+        );
+        let size = width as usize * height as usize;
+        Renderer {
+            width,
+            height,
+            buffer: vec![' '; size],
+            buffer_prev: vec![' '; size],
+            cbuffer: vec![theme.blank; size],
+            cbuffer_prev: vec![theme.blank; size],
+            samples: Samples::new(width, height, aa),
+            #[cfg(feature = "parallel")]
+            partial_samples: Vec::new(),
+            model: init::Model::default(),
+            spacing: 0.0,
+            theme,
+            edges: false,
+            labels: None,
+        }
+    }
+
+    /// Whether width x height characters with aa x aa samples each stay within the u16 sample
+    /// coordinates
+    pub fn fits(width: u16, height: u16, aa: u16) -> bool {
+        let max = usize::from(u16::MAX);
+        usize::from(width) * usize::from(aa) <= max && usize::from(height) * usize::from(aa) <= max
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Number of points the cube is sampled with
+    pub fn points(&self) -> usize {
+        self.model.len()
+    }
+
+    /// Changes the size of the frames, after which the next frame is drawn from scratch.
+    /// Panics like `Renderer::new` if the samples don't fit
+    pub fn resize(&mut self, width: u16, height: u16) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        *self = Renderer {
+            edges: self.edges,
+            labels: self.labels,
+            ..Renderer::new(width, height, self.samples.factor, self.theme.clone())
+        };
+        self.invalidate();
+    }
+
+    /// Forgets the previous frame so every character of the next one counts as changed
+    pub fn invalidate(&mut self) {
+        self.buffer.fill('\0');
+    }
+
+    /// Draws the scene, returning the new frame along with what changed since the last one
+    pub fn render(&mut self, scene: &Scene) -> Frame<'_> {
+        // Sample the cube more densely when it is drawn larger
+        let spacing = scene
+            .camera
+            .point_spacing(self.samples.width, self.samples.height);
+        if self.model.is_empty() || spacing != self.spacing {
+            self.spacing = spacing;
+            self.model.clear();
+            init::init(&mut self.model, spacing);
+        }
+
+        let (a, b, c) = scene.angles;
+        let trig_values = [a.sin(), a.cos(), b.sin(), b.cos(), c.sin(), c.cos()];
+        self.draw(scene, &trig_values);
+
+        Frame {
+            width: self.width,
+            height: self.height,
+            chars: &self.buffer,
+            colors: &self.cbuffer,
+            prev_chars: &self.buffer_prev,
+            prev_colors: &self.cbuffer_prev,
+        }
+    }
+
+    fn draw(&mut self, scene: &Scene, trig_values: &[f32]) {
+        let Scene {
+            cube,
+            turn,
+            camera,
+            lighting,
+            ..
+        } = scene;
+        let (model, samples, theme) = (&self.model, &mut self.samples, &self.theme);
+
+        self.buffer_prev.copy_from_slice(&self.buffer);
+        self.cbuffer_prev.copy_from_slice(&self.cbuffer);
+
+        self.buffer.fill(' ');
+        self.cbuffer.fill(theme.blank);
+        samples.clear();
+
+        let len = model.len();
+        let points_color = &model.points_color[..len];
+        let points_facelet = &model.points_facelet[..len];
+        let points_normal = &model.points_normal[..len];
+        let points_cubie = &model.points_cubie[..len];
+        let points_internal = &model.points_internal[..len];
+        let (turn_axis, turn_layers, turn_sin, turn_cos) = match *turn {
+            Some(Turn {
+                axis,
+                layers,
+                angle,
+            }) => (axis, layers, angle.sin(), angle.cos()),
+            None => (0, 0, 0.0, 1.0),
+        };
+
+        // Normals of the turning layers are rotated by the turn first
+        let rotate_normals = |turning: bool| -> Vec<Vector3f> {
+            model
+                .normals
+                .iter()
+                .map(|normal| {
+                    let normal = if turning {
+                        init::rotate_about(normal, turn_axis, turn_sin, turn_cos)
+                    } else {
+                        *normal
+                    };
+                    rotate(normal.x, normal.y, normal.z, trig_values)
+                })
+                .collect()
+        };
+        let normals = [rotate_normals(false), rotate_normals(true)];
+
+        // Unless lighting every point, surfaces are lit once per normal and sticker colour, as if
+        // the point was where the normal meets the unit sphere around the cube
+        let flat_luminance = |normals: &[Vector3f]| -> Vec<[f32; 6]> {
+            normals
+                .iter()
+                .map(|normal| {
+                    let position = Vector3f {
+                        x: normal.x * CUBE_SIZE / 2.0,
+                        y: normal.y * CUBE_SIZE / 2.0,
+                        z: normal.z * CUBE_SIZE / 2.0,
+                    };
+                    let view = camera.view_direction(&position);
+                    cube::Face::ALL.map(|face| lighting.shade(face, normal, &position, &view))
+                })
+                .collect()
+        };
+        let luminances = if lighting.per_pixel {
+            [Vec::new(), Vec::new()]
+        } else {
+            [flat_luminance(&normals[0]), flat_luminance(&normals[1])]
+        };
+
+        let transform = Transform::new(trig_values, camera, samples.width, samples.height);
+        let turning = |index: usize| {
+            turn.is_some() && turn_layers & (1 << (points_cubie[index][turn_axis] + 1)) != 0
+        };
+
+        // Draws a run of points into a set of samples, transforming them a block at a time
+        let splat = |run: Range<usize>, samples: &mut Samples| {
+            let mut turned = [[0.0; BLOCK]; 3];
+            let mut transformed = Transformed::default();
+            for start in run.clone().step_by(BLOCK) {
+                let block = start..(start + BLOCK).min(run.end);
+                let (x, y, z) = match turn {
+                    Some(_) => {
+                        for (offset, index) in block.clone().enumerate() {
+                            let mut point = model.point(index);
+                            if turning(index) {
+                                point = init::rotate_about(&point, turn_axis, turn_sin, turn_cos);
+                            }
+                            [turned[0][offset], turned[1][offset], turned[2][offset]] =
+                                [point.x, point.y, point.z];
+                        }
+                        let [x, y, z] = &turned;
+                        (&x[..block.len()], &y[..block.len()], &z[..block.len()])
+                    }
+                    None => (
+                        &model.points_x[block.clone()],
+                        &model.points_y[block.clone()],
+                        &model.points_z[block.clone()],
+                    ),
+                };
+                transform.apply(x, y, z, &mut transformed);
+
+                for (offset, index) in block.enumerate() {
+                    // Faces between cubies can only be seen while a layer is turned away from its
+                    // neighbour
+                    if points_internal[index] && turn.is_none() {
+                        continue;
+                    }
+                    let turning = turning(index);
+
+                    // Without stickers the plastic around them has the colour of the face
+                    let face = match points_facelet[index] {
+                        Some(facelet) if points_color[index] || !theme.stickers => {
+                            Some(cube.facelets[facelet as usize])
+                        }
+                        _ => None,
+                    };
+                    let color = face.map_or(theme.body, |face| theme.faces[face as usize]);
+                    // The plastic body is lit like a white surface
+                    let face = face.unwrap_or(cube::Face::U);
+
+                    let normal = points_normal[index] as usize;
+                    let luminance = if lighting.per_pixel {
+                        let rotated = Vector3f {
+                            x: transformed.x[offset],
+                            y: transformed.y[offset],
+                            z: transformed.z[offset],
+                        };
+                        let view = camera.view_direction(&rotated);
+                        lighting.shade(face, &normals[turning as usize][normal], &rotated, &view)
+                    } else {
+                        luminances[turning as usize][normal][face as usize]
+                    };
+                    update_samples(
+                        transformed.screen_x[offset],
+                        transformed.screen_y[offset],
+                        transformed.ooz[offset],
+                        samples,
+                        color,
+                        luminance,
+                    );
+                }
+            }
+        };
+
+        #[cfg(feature = "parallel")]
+        let parallel = rayon::current_num_threads() > 1;
+        #[cfg(not(feature = "parallel"))]
+        let parallel = false;
+
+        if !parallel {
+            splat(0..len, samples);
+        }
+
+        // Each thread draws a run of the points into samples of its own. Merging them in order
+        // keeps the point drawn first when two are equally near, as drawing them in one go does
+        #[cfg(feature = "parallel")]
+        if parallel {
+            use rayon::prelude::*;
+
+            let threads = rayon::current_num_threads();
+            let per_thread = len.div_ceil(threads).max(1);
+            if self.partial_samples.len() != threads {
+                self.partial_samples = (0..threads)
+                    .map(|_| Samples::new(self.width, self.height, samples.factor))
+                    .collect();
+            }
+            self.partial_samples
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(thread, partial)| {
+                    partial.clear();
+                    let start = (thread * per_thread).min(len);
+                    splat(start..(start + per_thread).min(len), partial);
+                });
+            for partial in &self.partial_samples {
+                samples.merge(partial);
+            }
+        }
+
+        samples.resolve(&mut self.buffer, &mut self.cbuffer, theme);
+        if self.edges {
+            edges::outline(
+                &mut self.buffer,
+                &self.cbuffer,
+                samples,
+                camera.aspect,
+                theme.body,
+            );
+        }
+        if let Some(labels) = self.labels {
+            let context = labels::Context {
+                buffer: &mut self.buffer,
+                cbuffer: &mut self.cbuffer,
+                samples,
+                camera,
+                trig_values,
+            };
+            labels::draw(context, cube, *turn, theme, labels);
+        }
+    }
+}
+
+fn update_samples(
+    x: f32,
+    y: f32,
+    ooz: f32,
+    samples: &mut Samples,
+    color: &'static str,
+    luminance: f32,
+) {
+    assert!(luminance <= 1.0);
+
+    // Zoomed in views can put points off screen on either side
+    if x < 0.0 || y < 0.0 || x >= samples.width as f32 {
+        return;
+    }
+    let xp: u32 = x as u32;
+    let yp: u32 = y as u32;
+
+    let index: usize = (xp + yp * samples.width as u32).try_into().unwrap();
+    let index_limit: usize = samples.zbuffer.len();
+
+    // Luminance ranges from 0 for unlit surfaces to 1 for fully lit ones
+    if index < index_limit && ooz > samples.zbuffer[index] {
+        samples.zbuffer[index] = ooz;
+        samples.cbuffer[index] = color;
+        samples.lbuffer[index] = luminance;
+    }
+}
+
+impl Frame<'_> {
+    /// Redraws the characters that changed since the previous frame
+    pub fn write_changes(&self, handle: impl Write, use_color: bool) -> io::Result<()> {
+        encoder::write_changes(self, handle, use_color)
+    }
+
+    /// Writes the whole frame as lines of text without trailing spaces, colouring them if asked
+    pub fn write_plain(&self, mut handle: impl Write, use_color: bool) -> io::Result<()> {
+        for (row, colors) in self
+            .chars
+            .chunks(self.width as usize)
+            .zip(self.colors.chunks(self.width as usize))
+        {
+            let len = row
+                .iter()
+                .rposition(|&val| val != ' ')
+                .map_or(0, |last| last + 1);
+            let mut prev_color = "";
+            for (&val, &color) in row[..len].iter().zip(colors) {
+                if use_color && val != ' ' && color != prev_color {
+                    write!(handle, "{}", color)?;
+                    prev_color = color;
+                }
+                write!(handle, "{}", val)?;
+            }
+            if !prev_color.is_empty() {
+                write!(handle, "{}", color::RESET)?;
+            }
+            writeln!(handle)?;
+        }
+        Ok(())
+    }
+}
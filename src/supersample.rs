@@ -0,0 +1,85 @@
+use crate::theme::Theme;
+
+// Depth, luminance and colour of each sample, with `factor` x `factor` samples per
+// character cell
+pub struct Samples {
+    pub factor: u16,
+    pub width: u16,
+    pub height: u16,
+    pub zbuffer: Vec<f32>,
+    pub lbuffer: Vec<f32>,
+    pub cbuffer: Vec<&'static str>,
+}
+
+impl Samples {
+    // Panics unless the samples fit in u16 on both sides, see `Renderer::fits`
+    pub fn new(width: u16, height: u16, factor: u16) -> Self {
+        let (width, height) = (
+            usize::from(width) * usize::from(factor),
+            usize::from(height) * usize::from(factor),
+        );
+        let size = width * height;
+        Samples {
+            factor,
+            width: u16::try_from(width).expect("too many samples across the frame"),
+            height: u16::try_from(height).expect("too many samples down the frame"),
+            zbuffer: vec![0.0; size],
+            lbuffer: vec![0.0; size],
+            cbuffer: vec![""; size],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.zbuffer.fill(0.0);
+    }
+
+    // Takes over the samples of `other` that are nearer, keeping these where both are equal
+    #[cfg(feature = "parallel")]
+    pub fn merge(&mut self, other: &Samples) {
+        for (index, &ooz) in other.zbuffer.iter().enumerate() {
+            if ooz > self.zbuffer[index] {
+                self.zbuffer[index] = ooz;
+                self.cbuffer[index] = other.cbuffer[index];
+                self.lbuffer[index] = other.lbuffer[index];
+            }
+        }
+    }
+
+    // Resolves the samples of each cell into a character by coverage: the ramp character is
+    // picked from the luminance averaged over the whole cell, counting uncovered samples as
+    // dark, and the colour is the one covering the most samples
+    pub fn resolve(&self, buffer: &mut [char], cbuffer: &mut [&str], theme: &Theme) {
+        let factor = self.factor as usize;
+        let cell_width = self.width as usize / factor;
+        let cell_samples = (factor * factor) as f32;
+        let mut colors: Vec<(&str, usize)> = Vec::with_capacity(factor * factor);
+
+        for (index, (val, color)) in buffer.iter_mut().zip(cbuffer.iter_mut()).enumerate() {
+            let (cx, cy) = (index % cell_width * factor, index / cell_width * factor);
+            let mut luminance = 0.0;
+            colors.clear();
+
+            for y in cy..cy + factor {
+                for x in cx..cx + factor {
+                    let sample = y * self.width as usize + x;
+                    if self.zbuffer[sample] == 0.0 {
+                        continue;
+                    }
+                    luminance += self.lbuffer[sample].max(0.0);
+                    let sample_color = self.cbuffer[sample];
+                    match colors.iter_mut().find(|(c, _)| *c == sample_color) {
+                        Some((_, count)) => *count += 1,
+                        None => colors.push((sample_color, 1)),
+                    }
+                }
+            }
+
+            // Ties go to the colour seen first
+            let majority = colors.iter().rev().max_by_key(|(_, count)| *count);
+            if let Some(&(majority_color, _)) = majority {
+                *val = theme.shade(luminance / cell_samples);
+                *color = majority_color;
+            }
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use crate::parse_angles;
+use rs_cube::camera::{self, Camera};
+use rs_cube::cube::{self, Face};
+use rs_cube::{init, project};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+const BODY_FILL: &str = "#000000";
+const MASK_FILL: &str = "#404040";
+const ARROW_FILL: &str = "#333333";
+const STICKER_INSET: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum View {
+    /// 3D projection of the cube
+    #[value(alias = "3d")]
+    Normal,
+    /// U face from above with the sides of the last layer around it
+    Plan,
+    /// Unfolded cross with U above, L F R B in a row and D below
+    Net,
+}
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Stage {
+    Fl,
+    Cross,
+    F2l,
+    F2b,
+    Line,
+    Ll,
+    Cll,
+    Ell,
+    Oll,
+    Ocll,
+    Oell,
+    Coll,
+}
+
+#[derive(clap::Args)]
+pub struct ExportArgs {
+    /// Output file, `.svg` for an image or `.html` for a page embedding it
+    #[arg(short, long)]
+    out: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = View::Normal)]
+    view: View,
+
+    /// Grey out facelets that are not part of this solving stage
+    #[arg(long, value_enum)]
+    stage: Option<Stage>,
+
+    /// Arrows between facelets, e.g. "U0U2,U2U8,U8U0-red"
+    #[arg(long = "arw", visible_alias = "arrows")]
+    arrows: Option<String>,
+
+    /// Show the case solved by this algorithm, i.e. apply its inverse
+    #[arg(long, allow_hyphen_values = true)]
+    case: Option<String>,
+
+    /// Image size in pixels
+    #[arg(long, default_value_t = 256u32, value_parser = clap::value_parser!(u32).range(1..=u16::MAX as i64))]
+    size: u32,
+
+    /// Background colour as "#RRGGBB", "#RGB" or a name such as "white", "none" for transparent
+    #[arg(long, default_value = "none", value_parser = parse_color)]
+    bg: String,
+
+    /// Rotation angles "a,b,c" of the 3D view in radians
+    #[arg(long, value_parser = parse_angles, allow_hyphen_values = true)]
+    angles: Option<(f32, f32, f32)>,
+}
+
+struct Arrow {
+    from: usize,
+    to: usize,
+    fill: String,
+}
+
+struct Polygon {
+    points: Vec<(f32, f32)>,
+    fill: &'static str,
+}
+
+// Vector drawing of one view: plastic body, stickers and the 2D centre of every visible facelet
+struct Drawing {
+    body: Vec<Polygon>,
+    stickers: Vec<Polygon>,
+    centers: [Option<(f32, f32)>; 54],
+    sticker_size: f32,
+}
+
+fn face_fill(face: Face) -> &'static str {
+    match face {
+        Face::U => "#FFFFFF",
+        Face::R => "#EE0000",
+        Face::F => "#00D800",
+        Face::D => "#FEFE00",
+        Face::L => "#FFA100",
+        Face::B => "#0000F2",
+    }
+}
+
+fn in_stage(stage: Stage, index: usize) -> bool {
+    let (pos, _) = cube::facelet_geometry(index);
+    let kind = pos.iter().filter(|&&v| v != 0).count(); // 1 centre, 2 edge, 3 corner
+    let (x, y) = (pos[0], pos[1]);
+
+    match stage {
+        Stage::Fl => y == -1,
+        Stage::Cross => (y == -1 && kind == 2) || kind == 1,
+        Stage::F2l => y <= 0 || kind == 1,
+        Stage::F2b => y <= 0 && x != 0,
+        Stage::Line => y == -1 && x == 0,
+        Stage::Ll | Stage::Oll | Stage::Coll => y == 1,
+        Stage::Cll | Stage::Ocll => y == 1 && kind != 2,
+        Stage::Ell | Stage::Oell => y == 1 && kind != 3,
+    }
+}
+
+// Orientation stages only show whether a last layer sticker has the U colour
+fn orientation_only(stage: Stage, index: usize) -> bool {
+    let (pos, _) = cube::facelet_geometry(index);
+    let kind = pos.iter().filter(|&&v| v != 0).count();
+
+    match stage {
+        Stage::Oll | Stage::Ocll | Stage::Oell => true,
+        Stage::Coll => kind == 2,
+        _ => false,
+    }
+}
+
+fn facelet_fill(cube: &cube::Cube, stage: Option<Stage>, index: usize) -> &'static str {
+    let face = cube.facelets[index];
+    match stage {
+        Some(stage) if !in_stage(stage, index) => MASK_FILL,
+        Some(stage) if orientation_only(stage, index) && face != cube.facelets[4] => MASK_FILL,
+        _ => face_fill(face),
+    }
+}
+
+fn parse_facelet(token: &str) -> Result<usize, String> {
+    let mut chars = token.chars();
+    let face = chars
+        .next()
+        .and_then(Face::from_char)
+        .ok_or(format!("invalid facelet \"{}\"", token))?;
+    match chars.as_str().parse::<usize>() {
+        Ok(index) if index < 9 => Ok(face as usize * 9 + index),
+        _ => Err(format!("invalid facelet \"{}\"", token)),
+    }
+}
+
+// A colour as SVG takes it, "#RGB", "#RRGGBB" or a name, which keeps anything that could
+// break out of the attribute out of the file
+fn parse_color(s: &str) -> Result<String, String> {
+    let valid = match s.strip_prefix('#') {
+        Some(hex) => matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => !s.is_empty() && s.chars().all(|c| c.is_ascii_alphabetic()),
+    };
+    match valid {
+        true => Ok(s.to_string()),
+        false => Err(format!(
+            "expected a colour as #RGB, #RRGGBB or a name, got \"{}\"",
+            s
+        )),
+    }
+}
+
+fn parse_arrows(arrows: &str) -> Result<Vec<Arrow>, String> {
+    let mut parsed = Vec::new();
+    for arrow in arrows.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+        let (path, fill) = match arrow.split_once('-') {
+            Some((path, fill)) => (path, parse_color(fill)?),
+            None => (arrow, ARROW_FILL.to_string()),
+        };
+
+        // Split "U0U2" into facelet tokens at each face letter
+        let starts: Vec<usize> = path
+            .char_indices()
+            .filter(|(_, c)| c.is_ascii_uppercase())
+            .map(|(i, _)| i)
+            .collect();
+        if starts.len() != 2 {
+            return Err(format!("arrow \"{}\" must join two facelets", arrow));
+        }
+        parsed.push(Arrow {
+            from: parse_facelet(&path[starts[0]..starts[1]])?,
+            to: parse_facelet(&path[starts[1]..])?,
+            fill,
+        });
+    }
+    Ok(parsed)
+}
+
+fn centroid(points: &[(f32, f32)]) -> (f32, f32) {
+    let n = points.len() as f32;
+    let (x, y) = points
+        .iter()
+        .fold((0.0, 0.0), |(ax, ay), (x, y)| (ax + x, ay + y));
+    (x / n, y / n)
+}
+
+fn inset(points: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let (cx, cy) = centroid(points);
+    points
+        .iter()
+        .map(|(x, y)| (x + (cx - x) * STICKER_INSET, y + (cy - y) * STICKER_INSET))
+        .collect()
+}
+
+fn draw_net(cube: &cube::Cube, stage: Option<Stage>) -> Drawing {
+    let mut drawing = Drawing {
+        body: Vec::new(),
+        stickers: Vec::new(),
+        centers: [None; 54],
+        sticker_size: 1.0,
+    };
+
+    for face in Face::ALL {
+        let (col, row) = match face {
+            Face::U => (1.0, 0.0),
+            Face::L => (0.0, 1.0),
+            Face::F => (1.0, 1.0),
+            Face::R => (2.0, 1.0),
+            Face::B => (3.0, 1.0),
+            Face::D => (1.0, 2.0),
+        };
+        let (ox, oy) = (col * 3.0, row * 3.0);
+        drawing.body.push(Polygon {
+            points: vec![
+                (ox, oy),
+                (ox + 3.0, oy),
+                (ox + 3.0, oy + 3.0),
+                (ox, oy + 3.0),
+            ],
+            fill: BODY_FILL,
+        });
+
+        for i in 0..9 {
+            let index = face as usize * 9 + i;
+            let (x, y) = (ox + (i % 3) as f32, oy + (i / 3) as f32);
+            let square = [(x, y), (x + 1.0, y), (x + 1.0, y + 1.0), (x, y + 1.0)];
+            drawing.stickers.push(Polygon {
+                points: inset(&square),
+                fill: facelet_fill(cube, stage, index),
+            });
+            drawing.centers[index] = Some((x + 0.5, y + 0.5));
+        }
+    }
+    drawing
+}
+
+fn draw_plan(cube: &cube::Cube, stage: Option<Stage>) -> Drawing {
+    let mut drawing = Drawing {
+        body: Vec::new(),
+        stickers: Vec::new(),
+        centers: [None; 54],
+        sticker_size: 1.0,
+    };
+
+    // Looking down at U with F at the bottom; last layer side stickers are folded out flat
+    let fold = |pos: [f32; 3], normal: [i8; 3]| -> (f32, f32) {
+        let out = (0.5 - pos[1]) * 0.5 + if normal[1] == 0 { 0.03 } else { 0.0 };
+        (
+            (pos[0] + normal[0] as f32 * out) * 3.0,
+            (pos[2] + normal[2] as f32 * out) * 3.0,
+        )
+    };
+
+    drawing.body.push(Polygon {
+        points: vec![(-1.5, -1.5), (1.5, -1.5), (1.5, 1.5), (-1.5, 1.5)],
+        fill: BODY_FILL,
+    });
+
+    for index in 0..54 {
+        let (pos, normal) = cube::facelet_geometry(index);
+        if pos[1] != 1 {
+            continue;
+        }
+        let corners: Vec<(f32, f32)> = cube::facelet_corners(index)
+            .iter()
+            .map(|&corner| fold(corner, normal))
+            .collect();
+        drawing.centers[index] = Some(centroid(&corners));
+        drawing.stickers.push(Polygon {
+            points: inset(&corners),
+            fill: facelet_fill(cube, stage, index),
+        });
+    }
+    drawing
+}
+
+fn draw_normal(
+    cube: &cube::Cube,
+    stage: Option<Stage>,
+    size: u32,
+    angles: (f32, f32, f32),
+    camera: &Camera,
+) -> Drawing {
+    let mut drawing = Drawing {
+        body: Vec::new(),
+        stickers: Vec::new(),
+        centers: [None; 54],
+        sticker_size: 0.0,
+    };
+
+    let (a, b, c) = angles;
+    let trig_values: Vec<f32> = vec![a.sin(), a.cos(), b.sin(), b.cos(), c.sin(), c.cos()];
+    let to_screen = |corner: [f32; 3]| -> (f32, f32) {
+        let point = init::to_point(corner);
+        let (x, y, _) = project(
+            point.x,
+            point.y,
+            point.z,
+            size as u16,
+            size as u16,
+            &trig_values,
+            camera,
+        );
+        (x, y)
+    };
+
+    for face in Face::ALL {
+        let first = face as usize * 9;
+        let outline: Vec<(f32, f32)> = [(0, 0), (2, 1), (8, 2), (6, 3)]
+            .iter()
+            .map(|&(i, corner)| to_screen(cube::facelet_corners(first + i)[corner]))
+            .collect();
+
+        // Faces wound clockwise on screen are facing the viewer
+        let area: f32 = (0..4)
+            .map(|i| {
+                let (p, q) = (outline[i], outline[(i + 1) % 4]);
+                p.0 * q.1 - q.0 * p.1
+            })
+            .sum();
+        if area <= 0.0 {
+            continue;
+        }
+        drawing.sticker_size = drawing.sticker_size.max((area / 2.0).sqrt() / 3.0);
+        drawing.body.push(Polygon {
+            points: outline,
+            fill: BODY_FILL,
+        });
+
+        for index in first..first + 9 {
+            let corners: Vec<(f32, f32)> = cube::facelet_corners(index)
+                .iter()
+                .map(|&corner| to_screen(corner))
+                .collect();
+            drawing.centers[index] = Some(centroid(&corners));
+            drawing.stickers.push(Polygon {
+                points: inset(&corners),
+                fill: facelet_fill(cube, stage, index),
+            });
+        }
+    }
+    drawing
+}
+
+fn write_polygon(svg: &mut String, polygon: &Polygon) {
+    let points: Vec<String> = polygon
+        .points
+        .iter()
+        .map(|(x, y)| format!("{:.3},{:.3}", x, y))
+        .collect();
+    writeln!(
+        svg,
+        "  <polygon points=\"{}\" fill=\"{}\"/>",
+        points.join(" "),
+        polygon.fill
+    )
+    .unwrap();
+}
+
+fn write_arrow(svg: &mut String, from: (f32, f32), to: (f32, f32), fill: &str, unit: f32) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length < unit * 0.5 {
+        return;
+    }
+    let (ux, uy) = (dx / length, dy / length);
+
+    // Leave a little of each sticker uncovered at both ends
+    let start = (from.0 + ux * unit * 0.2, from.1 + uy * unit * 0.2);
+    let tip = (to.0 - ux * unit * 0.2, to.1 - uy * unit * 0.2);
+    let head = unit * 0.35;
+    let base = (tip.0 - ux * head, tip.1 - uy * head);
+
+    writeln!(
+        svg,
+        "  <line x1=\"{:.3}\" y1=\"{:.3}\" x2=\"{:.3}\" y2=\"{:.3}\" stroke=\"{}\" stroke-width=\"{:.3}\"/>",
+        start.0,
+        start.1,
+        base.0,
+        base.1,
+        fill,
+        unit * 0.1
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        "  <polygon points=\"{:.3},{:.3} {:.3},{:.3} {:.3},{:.3}\" fill=\"{}\"/>",
+        tip.0,
+        tip.1,
+        base.0 - uy * head * 0.5,
+        base.1 + ux * head * 0.5,
+        base.0 + uy * head * 0.5,
+        base.1 - ux * head * 0.5,
+        fill
+    )
+    .unwrap();
+}
+
+fn to_svg(drawing: &Drawing, arrows: &[Arrow], size: u32, bg: &str) -> String {
+    let all_points = drawing
+        .body
+        .iter()
+        .chain(&drawing.stickers)
+        .flat_map(|polygon| &polygon.points);
+    let (min_x, min_y, max_x, max_y) = all_points.fold(
+        (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+        |(x0, y0, x1, y1), &(x, y)| (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+    );
+    let margin = drawing.sticker_size * 0.3;
+    let (x, y) = (min_x - margin, min_y - margin);
+    let (w, h) = (max_x - min_x + 2.0 * margin, max_y - min_y + 2.0 * margin);
+    let height = (size as f32 * h / w).round() as u32;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{:.3} {:.3} {:.3} {:.3}\">",
+        size, height, x, y, w, h
+    )
+    .unwrap();
+    if bg != "none" {
+        writeln!(
+            svg,
+            "  <rect x=\"{:.3}\" y=\"{:.3}\" width=\"{:.3}\" height=\"{:.3}\" fill=\"{}\"/>",
+            x, y, w, h, bg
+        )
+        .unwrap();
+    }
+    for polygon in drawing.body.iter().chain(&drawing.stickers) {
+        write_polygon(&mut svg, polygon);
+    }
+    for arrow in arrows {
+        if let (Some(from), Some(to)) = (drawing.centers[arrow.from], drawing.centers[arrow.to]) {
+            write_arrow(&mut svg, from, to, &arrow.fill, drawing.sticker_size);
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub fn export(
+    args: &ExportArgs,
+    cube: &cube::Cube,
+    camera_args: &crate::CameraArgs,
+) -> Result<(), String> {
+    let is_html = match args.out.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("svg") => false,
+        Some(ext) if ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm") => true,
+        _ => return Err("output file must end in .svg or .html".to_string()),
+    };
+
+    let mut cube = cube.clone();
+    if let Some(case) = &args.case {
+        cube.apply_alg(&cube::invert_alg(&cube::parse_alg(case)?));
+    }
+    let arrows = match &args.arrows {
+        Some(arrows) => parse_arrows(arrows)?,
+        None => Vec::new(),
+    };
+
+    let drawing = match args.view {
+        View::Net => draw_net(&cube, args.stage),
+        View::Plan => draw_plan(&cube, args.stage),
+        View::Normal => draw_normal(
+            &cube,
+            args.stage,
+            args.size,
+            args.angles.unwrap_or(camera::ISOMETRIC_ANGLES),
+            // SVG pixels are square
+            &Camera {
+                aspect: 1.0,
+                ..camera_args.camera(1.0)
+            },
+        ),
+    };
+    let svg = to_svg(&drawing, &arrows, args.size, &args.bg);
+
+    let contents = if is_html {
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>rs-cube</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+            svg
+        )
+    } else {
+        svg
+    };
+    std::fs::write(&args.out, contents).map_err(|e| format!("{}: {}", args.out.display(), e))
+}
@@ -0,0 +1,156 @@
+use crate::input;
+use libc::{c_int, raise, signal, SIGCONT, SIGHUP, SIGINT, SIGTERM, SIGTSTP, SIG_DFL};
+use rs_cube::{ansi_escape_code, color};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Set by ^C, SIGTERM, SIGHUP or a quit key, checked once per frame
+pub static EXIT_REQUESTED: AtomicBool = AtomicBool::new(false);
+// Set by ^Z, handled between frames by `TerminalGuard::suspend_if_requested`
+static SUSPEND_REQUESTED: AtomicBool = AtomicBool::new(false);
+// Set when the process is continued, after which the screen may have been changed
+static REPAINT_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+// Whether the terminal is taken over, so it is only restored once
+static ACTIVE: Mutex<bool> = Mutex::new(false);
+
+extern "C" fn handle_exit_signal(_: c_int) {
+    EXIT_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+extern "C" fn handle_suspend(_: c_int) {
+    SUSPEND_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+extern "C" fn handle_continue(_: c_int) {
+    REPAINT_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+pub fn install_signal_handlers() {
+    unsafe {
+        for sig in [SIGINT, SIGTERM, SIGHUP] {
+            signal(sig, handle_exit_signal as extern "C" fn(c_int) as usize);
+        }
+    }
+}
+
+pub fn exit_requested() -> bool {
+    EXIT_REQUESTED.load(Ordering::Relaxed)
+}
+
+// Longest wait for the terminal to answer queries, as replies come back over SSH too
+const QUERY_TIMEOUT: Duration = Duration::from_millis(300);
+
+// Asks the terminal whether it knows synchronized updates (DEC mode 2026), which hold back
+// drawing until a whole frame has arrived. Every terminal answers the device attributes query
+// sent after it, so terminals ignoring the mode query don't make us wait for the timeout.
+// Only works while the terminal is taken over and reads keys unbuffered
+pub fn supports_synchronized_output() -> bool {
+    print!(
+        "{}{}",
+        ansi_escape_code::RequestSynchronizedUpdateMode,
+        ansi_escape_code::RequestDeviceAttributes
+    );
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+    let reply = input::read_reply(QUERY_TIMEOUT, |reply| {
+        // Device attributes come back last as ESC [ ? ... c
+        reply.ends_with(b"c") && reply.windows(3).any(|w| w == b"\x1B[?")
+    });
+    // ESC [ ? 2026 ; Ps $ y where Ps is 1 or 2 for set or reset and 3 for always set
+    let prefix = b"\x1B[?2026;";
+    reply
+        .windows(prefix.len() + 3)
+        .any(|w| w.starts_with(prefix) && matches!(&w[prefix.len()..], b"1$y" | b"2$y" | b"3$y"))
+}
+
+// Puts the terminal back the way it was found: cooked input, main screen, visible cursor
+fn restore() {
+    let mut active = ACTIVE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if !*active {
+        return;
+    }
+    *active = false;
+    input::disable_raw_mode();
+    print!(
+        "{}{}{}{}",
+        ansi_escape_code::EraseScreen,
+        ansi_escape_code::DisableAltBuffer,
+        color::RESET,
+        ansi_escape_code::CursorVisible
+    );
+    let _ = io::stdout().flush();
+}
+
+// Owns the terminal while the animation runs. Dropping it, panicking or being suspended
+// with ^Z restores the terminal, and `fg` takes it over again
+pub struct TerminalGuard {
+    // Printed before erasing the screen so it fills with the theme's background
+    background: Option<&'static str>,
+}
+
+impl TerminalGuard {
+    pub fn enter(background: Option<&'static str>) -> Self {
+        let guard = TerminalGuard { background };
+        guard.take_over();
+
+        // Restore before the panic message is printed so it lands on the main screen, then
+        // exit without unwinding, which would flush half a frame onto it
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore();
+            default_hook(info);
+            std::process::exit(101);
+        }));
+        unsafe {
+            signal(SIGTSTP, handle_suspend as extern "C" fn(c_int) as usize);
+            signal(SIGCONT, handle_continue as extern "C" fn(c_int) as usize);
+        }
+        guard
+    }
+
+    fn take_over(&self) {
+        *ACTIVE
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = true;
+        input::enable_raw_mode();
+        print!("{}", ansi_escape_code::EnableAltBuffer);
+        if let Some(background) = self.background {
+            print!("{}", background);
+        }
+        print!("{}", ansi_escape_code::EraseScreen);
+        print!("{}", ansi_escape_code::CursorInvisible);
+        let _ = io::stdout().flush();
+    }
+
+    // Stops the process if ^Z was pressed, restoring the terminal first and taking it over
+    // again once continued. Returns whether the screen has to be drawn again from scratch
+    pub fn suspend_if_requested(&self) -> bool {
+        if SUSPEND_REQUESTED.swap(false, Ordering::Relaxed) {
+            restore();
+            // Stop for real with the default action, which returns once continued
+            unsafe {
+                signal(SIGTSTP, SIG_DFL);
+                raise(SIGTSTP);
+                signal(SIGTSTP, handle_suspend as extern "C" fn(c_int) as usize);
+            }
+            self.take_over();
+        }
+        REPAINT_REQUESTED.swap(false, Ordering::Relaxed)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+        unsafe {
+            signal(SIGTSTP, SIG_DFL);
+            signal(SIGCONT, SIG_DFL);
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use crate::color;
+use std::fs;
+use std::path::Path;
+
+pub const NAMES: [&str; 8] = [
+    "classic",
+    "stickerless",
+    "monochrome",
+    "high-contrast",
+    "japanese",
+    "protanopia",
+    "deuteranopia",
+    "tritanopia",
+];
+
+#[derive(Clone)]
+pub struct Theme {
+    // Shading characters from darkest to brightest
+    pub ramp: Vec<char>,
+    // Sticker colours indexed by face
+    pub faces: [&'static str; 6],
+    // Colour of the plastic around the stickers
+    pub body: &'static str,
+    // Stickerless cubes are coloured all the way to the edges of each cubie
+    pub stickers: bool,
+    pub background: Option<[u8; 3]>,
+    // Colour code for blank cells, clearing the foreground and keeping the background
+    pub blank: &'static str,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            ramp: ".,-~:;=!*#$@".chars().collect(),
+            faces: [
+                color::WHITE,
+                color::RED,
+                color::GREEN,
+                color::YELLOW,
+                color::BOLD_RED,
+                color::BLUE,
+            ],
+            body: color::BLACK,
+            stickers: true,
+            background: None,
+            blank: color::RESET,
+        }
+    }
+}
+
+impl Theme {
+    pub fn builtin(name: &str) -> Option<Self> {
+        let classic = Theme::default();
+        let theme = match name {
+            "classic" => classic,
+            "stickerless" => Theme {
+                stickers: false,
+                ..classic
+            },
+            "monochrome" => Theme {
+                faces: [color::WHITE; 6],
+                ..classic
+            },
+            "high-contrast" => Theme {
+                ramp: ".:-=+*#%@".chars().collect(),
+                faces: [
+                    color::BOLD_WHITE,
+                    color::BOLD_RED,
+                    color::BOLD_GREEN,
+                    color::BOLD_YELLOW,
+                    color::true_color([0xFF, 0x8C, 0x00]),
+                    color::BOLD_BLUE,
+                ],
+                background: Some([0x00, 0x00, 0x00]),
+                ..classic
+            },
+            // White opposite blue and green opposite yellow
+            "japanese" => Theme {
+                faces: [
+                    color::WHITE,
+                    color::RED,
+                    color::GREEN,
+                    color::BLUE,
+                    color::BOLD_RED,
+                    color::YELLOW,
+                ],
+                ..classic
+            },
+            // Colour-blind palettes keep every pair of faces apart in lightness or along the
+            // blue-yellow axis (red-green for tritanopia), based on the Okabe-Ito colours
+            "protanopia" => Theme {
+                faces: palette([
+                    0xFFFFFF, // White
+                    0x8C3B00, // Dark vermilion, as red looks darker without L cones
+                    0x009E73, // Bluish green
+                    0xF0E442, // Yellow
+                    0xE69F00, // Orange
+                    0x0072B2, // Blue
+                ]),
+                ..classic
+            },
+            "deuteranopia" => Theme {
+                faces: palette([
+                    0xFFFFFF, // White
+                    0xA33500, // Vermilion
+                    0x00A0A0, // Teal
+                    0xF0E442, // Yellow
+                    0xE69F00, // Orange
+                    0x0050A0, // Blue
+                ]),
+                ..classic
+            },
+            "tritanopia" => Theme {
+                faces: palette([
+                    0xFFFFFF, // White
+                    0xD00000, // Red
+                    0x00A050, // Green
+                    0xFFA0A0, // Pink, as yellow looks pink without S cones
+                    0x804000, // Brown
+                    0x20A0E0, // Sky blue, lighter than the green
+                ]),
+                ..classic
+            },
+            _ => return None,
+        };
+        let background = theme.background;
+        Some(theme.with_background(background))
+    }
+
+    fn with_background(self, background: Option<[u8; 3]>) -> Self {
+        let blank = match background {
+            Some(rgb) => {
+                let code = format!("{}{}", color::RESET, color::true_color_background(rgb));
+                Box::leak(code.into_boxed_str())
+            }
+            None => color::RESET,
+        };
+        Theme {
+            background,
+            blank,
+            ..self
+        }
+    }
+
+    // Character for a luminance from 0 to 1
+    pub fn shade(&self, luminance: f32) -> char {
+        self.ramp[(luminance * (self.ramp.len() - 1) as f32) as usize]
+    }
+}
+
+fn palette(faces: [u32; 6]) -> [&'static str; 6] {
+    faces.map(|rgb| color::true_color([16, 8, 0].map(|shift| (rgb >> shift) as u8)))
+}
+
+// A built-in theme name or the path of a theme file
+pub fn parse_theme(s: &str) -> Result<Theme, String> {
+    match Theme::builtin(s) {
+        Some(theme) => Ok(theme),
+        None if Path::new(s).is_file() => load(s),
+        None => Err(format!(
+            "unknown theme \"{}\", expected one of {} or a theme file",
+            s,
+            NAMES.join(", ")
+        )),
+    }
+}
+
+// Reads a theme file of `key = value` lines:
+//
+//     base = "classic"          # built-in theme to start from
+//     ramp = ".:-=+*#%@"        # darkest to brightest
+//     U = "white"               # per face, a colour name or "#RRGGBB"
+//     body = "black"
+//     background = "#1E1E2E"
+//     stickers = true
+fn load(path: &str) -> Result<Theme, String> {
+    let content = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+
+    let mut entries = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let error = |message: &str| format!("{}:{}: {}", path, number + 1, message);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected `key = value`"))?;
+        let value = parse_value(value.trim()).map_err(|message| error(&message))?;
+        entries.push((key.trim(), value, number + 1));
+    }
+
+    let mut theme = match entries.iter().find(|(key, _, _)| *key == "base") {
+        Some((_, base, number)) => Theme::builtin(base)
+            .ok_or_else(|| format!("{}:{}: unknown theme \"{}\"", path, number, base))?,
+        None => Theme::default(),
+    };
+    for (key, value, number) in entries {
+        let error = |message: String| format!("{}:{}: {}", path, number, message);
+        match key {
+            "base" => {}
+            "ramp" => {
+                if value.is_empty() || value.contains(char::is_whitespace) {
+                    return Err(error(
+                        "ramp needs at least one character and no spaces".into(),
+                    ));
+                }
+                theme.ramp = value.chars().collect();
+            }
+            "U" | "R" | "F" | "D" | "L" | "B" => {
+                let face = "URFDLB".find(key).unwrap();
+                theme.faces[face] = parse_color(&value).map_err(error)?;
+            }
+            "body" => theme.body = parse_color(&value).map_err(error)?,
+            "background" => theme = theme.with_background(Some(parse_rgb(&value).map_err(error)?)),
+            "stickers" => {
+                theme.stickers = match value.as_str() {
+                    "true" => true,
+                    "false" => false,
+                    _ => return Err(error("stickers must be true or false".into())),
+                }
+            }
+            _ => return Err(error(format!("unknown key \"{}\"", key))),
+        }
+    }
+    Ok(theme)
+}
+
+// A quoted string or a bare word, followed by an optional comment
+fn parse_value(value: &str) -> Result<String, String> {
+    let (value, rest) = match value.strip_prefix('"') {
+        Some(quoted) => quoted
+            .split_once('"')
+            .ok_or_else(|| "missing closing quote".to_string())?,
+        None => value.split_once('#').unwrap_or((value, "")),
+    };
+    let rest = rest.trim();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err(format!("unexpected \"{}\" after value", rest));
+    }
+    Ok(value.trim().to_string())
+}
+
+fn parse_color(s: &str) -> Result<&'static str, String> {
+    let named = match s {
+        "black" => color::BLACK,
+        "red" => color::RED,
+        "green" => color::GREEN,
+        "yellow" => color::YELLOW,
+        "blue" => color::BLUE,
+        "magenta" => color::MAGENTA,
+        "cyan" => color::CYAN,
+        "white" => color::WHITE,
+        "bright-black" => color::BOLD_BLACK,
+        "bright-red" => color::BOLD_RED,
+        "bright-green" => color::BOLD_GREEN,
+        "bright-yellow" => color::BOLD_YELLOW,
+        "bright-blue" => color::BOLD_BLUE,
+        "bright-magenta" => color::BOLD_MAGENTA,
+        "bright-cyan" => color::BOLD_CYAN,
+        "bright-white" => color::BOLD_WHITE,
+        _ => {
+            return parse_rgb(s)
+                .map(color::true_color)
+                .map_err(|_| format!("expected a colour name or \"#RRGGBB\", got \"{}\"", s))
+        }
+    };
+    Ok(named)
+}
+
+// "#RRGGBB", with or without the "#"
+pub fn parse_rgb(s: &str) -> Result<[u8; 3], String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    let value = Some(hex)
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| format!("expected a colour as \"#RRGGBB\", got \"{}\"", s))?;
+    Ok([16, 8, 0].map(|shift| (value >> shift) as u8))
+}
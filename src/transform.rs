@@ -0,0 +1,238 @@
+use crate::camera::{Camera, Projection};
+
+// Rotation by the cube's angles followed by the camera's projection, with everything that is
+// the same for every point worked out once per frame. Points go through in batches, four at
+// a time with SSE2 on x86_64 and one at a time elsewhere. Both give exactly the results of
+// `crate::project`, as every product and sum is taken in the same order
+#[derive(Clone, Copy)]
+pub struct Transform {
+    // Rotation matrix rows for x and y, with columns in the order (y, x, z) of `crate::rotate`
+    row_x: [f32; 3],
+    row_y: [f32; 3],
+    sin_b: f32,
+    cos_b: f32,
+    sin_c: f32,
+    cos_c: f32,
+    distance: f32,
+    aspect: f32,
+    focal_length: f32,
+    perspective: bool,
+    center: (f32, f32),
+}
+
+// Points rotated into view space and projected onto the screen, one entry per point
+#[derive(Default)]
+pub struct Transformed {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub z: Vec<f32>,
+    pub screen_x: Vec<f32>,
+    pub screen_y: Vec<f32>,
+    pub ooz: Vec<f32>,
+}
+
+impl Transformed {
+    fn resize(&mut self, len: usize) {
+        for v in [
+            &mut self.x,
+            &mut self.y,
+            &mut self.z,
+            &mut self.screen_x,
+            &mut self.screen_y,
+            &mut self.ooz,
+        ] {
+            v.resize(len, 0.0);
+        }
+    }
+}
+
+impl Transform {
+    pub fn new(trig_values: &[f32], camera: &Camera, width: u16, height: u16) -> Self {
+        let trig_values = &trig_values[..6];
+        let (sin_a, sin_b, sin_c) = (trig_values[0], trig_values[2], trig_values[4]);
+        let (cos_a, cos_b, cos_c) = (trig_values[1], trig_values[3], trig_values[5]);
+        let cos_a_sin_b = cos_a * sin_b;
+        let sin_a_sin_b = sin_a * sin_b;
+
+        Transform {
+            row_x: [
+                cos_a * cos_b,
+                cos_a_sin_b * sin_c - sin_a * cos_c,
+                cos_a_sin_b * cos_c + sin_a * sin_c,
+            ],
+            row_y: [
+                sin_a * cos_b,
+                sin_a_sin_b * sin_c + cos_a * cos_c,
+                sin_a_sin_b * cos_c - cos_a * sin_c,
+            ],
+            sin_b,
+            cos_b,
+            sin_c,
+            cos_c,
+            distance: camera.distance,
+            aspect: camera.aspect,
+            focal_length: camera.focal_length(width, height),
+            perspective: camera.projection == Projection::Perspective,
+            center: ((width as f32) / 2.0, (height as f32) / 2.0),
+        }
+    }
+
+    // Transforms the points with coordinates `x`, `y` and `z` into `out`
+    pub fn apply(&self, x: &[f32], y: &[f32], z: &[f32], out: &mut Transformed) {
+        assert!(y.len() == x.len() && z.len() == x.len());
+        out.resize(x.len());
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: SSE2 is always enabled on x86_64, and `x`, `y`, `z` and every vector of `out`
+        // have the same length, checked and resized just above
+        let done = unsafe { self.apply_sse2(x, y, z, out) };
+        #[cfg(not(target_arch = "x86_64"))]
+        let done = 0;
+        self.apply_scalar_from(done, x, y, z, out);
+    }
+
+    // Transforms the points one at a time
+    pub fn apply_scalar(&self, x: &[f32], y: &[f32], z: &[f32], out: &mut Transformed) {
+        assert!(y.len() == x.len() && z.len() == x.len());
+        out.resize(x.len());
+        self.apply_scalar_from(0, x, y, z, out);
+    }
+
+    fn apply_scalar_from(
+        &self,
+        start: usize,
+        x: &[f32],
+        y: &[f32],
+        z: &[f32],
+        out: &mut Transformed,
+    ) {
+        let [rx0, rx1, rx2] = self.row_x;
+        let [ry0, ry1, ry2] = self.row_y;
+        for index in start..x.len() {
+            let (i, j, k) = (x[index], y[index], z[index]);
+            let rx = rx0 * j + rx1 * i + rx2 * k;
+            let ry = ry0 * j + ry1 * i + ry2 * k;
+            let rz = -j * self.sin_b + i * self.cos_b * self.sin_c + k * self.cos_b * self.cos_c;
+
+            let ooz = 1.0 / (rz + self.distance);
+            let scale = match self.perspective {
+                true => self.focal_length * ooz,
+                false => self.focal_length / self.distance,
+            };
+            out.x[index] = rx;
+            out.y[index] = ry;
+            out.z[index] = rz;
+            out.screen_x[index] = self.center.0 + scale * rx;
+            out.screen_y[index] = self.center.1 - scale * ry / self.aspect;
+            out.ooz[index] = ooz;
+        }
+    }
+
+    // Transforms whole groups of four points, returning how many were done. SSE2 is part of
+    // every x86_64 CPU
+    //
+    // Safety: `y`, `z` and the vectors of `out` must be at least as long as `x`
+    #[cfg(target_arch = "x86_64")]
+    unsafe fn apply_sse2(&self, x: &[f32], y: &[f32], z: &[f32], out: &mut Transformed) -> usize {
+        use std::arch::x86_64::*;
+
+        let splat = |v: f32| _mm_set1_ps(v);
+        let [rx0, rx1, rx2] = self.row_x.map(splat);
+        let [ry0, ry1, ry2] = self.row_y.map(splat);
+        let (sin_b, cos_b) = (splat(self.sin_b), splat(self.cos_b));
+        let (sin_c, cos_c) = (splat(self.sin_c), splat(self.cos_c));
+        let (distance, aspect) = (splat(self.distance), splat(self.aspect));
+        let focal_length = splat(self.focal_length);
+        let fixed_scale = splat(self.focal_length / self.distance);
+        let (center_x, center_y) = (splat(self.center.0), splat(self.center.1));
+        let (one, sign) = (splat(1.0), splat(-0.0));
+
+        let done = x.len() / 4 * 4;
+        for index in (0..done).step_by(4) {
+            // SAFETY: index + 4 <= done <= x.len(), and the caller makes the other slices and
+            // vectors at least that long. The unaligned loads and stores need no alignment
+            let i = _mm_loadu_ps(x.as_ptr().add(index));
+            let j = _mm_loadu_ps(y.as_ptr().add(index));
+            let k = _mm_loadu_ps(z.as_ptr().add(index));
+
+            let rx = _mm_add_ps(
+                _mm_add_ps(_mm_mul_ps(rx0, j), _mm_mul_ps(rx1, i)),
+                _mm_mul_ps(rx2, k),
+            );
+            let ry = _mm_add_ps(
+                _mm_add_ps(_mm_mul_ps(ry0, j), _mm_mul_ps(ry1, i)),
+                _mm_mul_ps(ry2, k),
+            );
+            let rz = _mm_add_ps(
+                _mm_add_ps(
+                    _mm_mul_ps(_mm_xor_ps(j, sign), sin_b),
+                    _mm_mul_ps(_mm_mul_ps(i, cos_b), sin_c),
+                ),
+                _mm_mul_ps(_mm_mul_ps(k, cos_b), cos_c),
+            );
+
+            let ooz = _mm_div_ps(one, _mm_add_ps(rz, distance));
+            let scale = match self.perspective {
+                true => _mm_mul_ps(focal_length, ooz),
+                false => fixed_scale,
+            };
+            let screen_x = _mm_add_ps(center_x, _mm_mul_ps(scale, rx));
+            let screen_y = _mm_sub_ps(center_y, _mm_div_ps(_mm_mul_ps(scale, ry), aspect));
+
+            _mm_storeu_ps(out.x.as_mut_ptr().add(index), rx);
+            _mm_storeu_ps(out.y.as_mut_ptr().add(index), ry);
+            _mm_storeu_ps(out.z.as_mut_ptr().add(index), rz);
+            _mm_storeu_ps(out.screen_x.as_mut_ptr().add(index), screen_x);
+            _mm_storeu_ps(out.screen_y.as_mut_ptr().add(index), screen_y);
+            _mm_storeu_ps(out.ooz.as_mut_ptr().add(index), ooz);
+        }
+        done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Bit for bit the same as one point at a time, including the points after the last group
+    // of four
+    #[test]
+    fn batches_match_scalar() {
+        let count = 4 * 5 + 3;
+        let coord = |seed: usize| {
+            (0..count)
+                .map(|n| ((n * seed) % 17) as f32 / 4.0 - 2.0)
+                .collect::<Vec<f32>>()
+        };
+        let (x, y, z) = (coord(3), coord(5), coord(7));
+
+        let cameras = [
+            Camera::default(),
+            Camera::new(Projection::Perspective, 10.0, 2.0, 0.5),
+            Camera::new(Projection::Perspective, 120.0, 100.0, 4.0),
+            Camera::new(Projection::Orthographic, 60.0, 7.0, 1.5),
+            Camera::new(Projection::Isometric, 45.0, 3.5, 2.0),
+        ];
+        for camera in cameras {
+            for (a, b, c) in [(0.0, 0.0, 0.0), crate::START_ANGLES, (-2.1, 0.7, 3.0)] {
+                let trig_values = [a.sin(), a.cos(), b.sin(), b.cos(), c.sin(), c.cos()];
+                let transform = Transform::new(&trig_values, &camera, 80, 24);
+                let (mut batched, mut scalar) = (Transformed::default(), Transformed::default());
+                transform.apply(&x, &y, &z, &mut batched);
+                transform.apply_scalar(&x, &y, &z, &mut scalar);
+
+                for (batched, scalar) in [
+                    (&batched.x, &scalar.x),
+                    (&batched.y, &scalar.y),
+                    (&batched.z, &scalar.z),
+                    (&batched.screen_x, &scalar.screen_x),
+                    (&batched.screen_y, &scalar.screen_y),
+                    (&batched.ooz, &scalar.ooz),
+                ] {
+                    assert_eq!(batched.len(), count);
+                    let bits = |v: &Vec<f32>| v.iter().map(|f| f.to_bits()).collect::<Vec<_>>();
+                    assert_eq!(bits(batched), bits(scalar));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::color;
+use crate::renderer::{Frame, Renderer, Scene};
+use crate::theme::Theme;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::widgets::{StatefulWidget, Widget};
+
+// Draws a scene into a region of a ratatui buffer. Cells the cube doesn't cover are left
+// as they are, so it sits on top of whatever is behind it.
+//
+// As a `StatefulWidget` the state is a `Renderer`, which keeps its theme, edges and labels
+// and only samples the cube again when the area or zoom changes. As a plain `Widget` a
+// renderer with the classic theme is set up for every draw
+pub struct CubeWidget<'a> {
+    scene: &'a Scene,
+}
+
+impl<'a> CubeWidget<'a> {
+    pub fn new(scene: &'a Scene) -> Self {
+        CubeWidget { scene }
+    }
+}
+
+impl StatefulWidget for CubeWidget<'_> {
+    type State = Renderer;
+
+    fn render(self, area: Rect, buf: &mut Buffer, renderer: &mut Renderer) {
+        let area = area.intersection(buf.area);
+        if area.is_empty() {
+            return;
+        }
+        renderer.resize(area.width, area.height);
+        copy_frame(&renderer.render(self.scene), area, buf);
+    }
+}
+
+impl Widget for CubeWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut renderer = Renderer::new(area.width, area.height, 1, Theme::default());
+        StatefulWidget::render(self, area, buf, &mut renderer);
+    }
+}
+
+fn copy_frame(frame: &Frame, area: Rect, buf: &mut Buffer) {
+    for (index, (&val, &code)) in frame.chars.iter().zip(frame.colors).enumerate() {
+        if val == ' ' {
+            continue;
+        }
+        let x = area.x + (index % frame.width as usize) as u16;
+        let y = area.y + (index / frame.width as usize) as u16;
+        if let Some(cell) = buf.cell_mut((x, y)) {
+            cell.set_char(val).set_fg(to_color(code));
+        }
+    }
+}
+
+// Colour of an escape code from the `color` module or a theme
+fn to_color(code: &str) -> Color {
+    match code {
+        color::BLACK => Color::Black,
+        color::RED => Color::Red,
+        color::GREEN => Color::Green,
+        color::YELLOW => Color::Yellow,
+        color::BLUE => Color::Blue,
+        color::MAGENTA => Color::Magenta,
+        color::CYAN => Color::Cyan,
+        color::WHITE => Color::Gray,
+        color::BOLD_BLACK => Color::DarkGray,
+        color::BOLD_RED => Color::LightRed,
+        color::BOLD_GREEN => Color::LightGreen,
+        color::BOLD_YELLOW => Color::LightYellow,
+        color::BOLD_BLUE => Color::LightBlue,
+        color::BOLD_MAGENTA => Color::LightMagenta,
+        color::BOLD_CYAN => Color::LightCyan,
+        color::BOLD_WHITE => Color::White,
+        _ => {
+            let [r, g, b] = color::to_rgb(code);
+            Color::Rgb(r, g, b)
+        }
+    }
+}
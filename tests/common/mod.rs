@@ -0,0 +1,14 @@
+// Fixtures shared by the rendering tests
+use rs_cube::{cube, Cube, Scene};
+
+// Rotation that shows U, F and R along with a little of the faces behind them
+pub const ANGLES: (f32, f32, f32) = (0.4, -0.6, 2.3);
+
+// The solved cube after `alg`, held at `ANGLES`
+pub fn scene(alg: &str) -> Scene {
+    let mut cube = Cube::solved();
+    cube.apply_alg(&cube::parse_alg(alg).unwrap());
+    let mut scene = Scene::new(cube);
+    scene.angles = ANGLES;
+    scene
+}
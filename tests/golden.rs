@@ -0,0 +1,155 @@
+// Golden frames: each case is rendered through `Renderer::render` and checked against its file in
+// tests/golden, which holds the plain text of the frame and then its colours as the face each
+// colour belongs to (URFDLB, k for the body). Set RS_CUBE_BLESS=1 to rewrite the files after an
+// intended change
+mod common;
+
+use common::scene;
+use rs_cube::camera::Projection;
+use rs_cube::cube::Face;
+use rs_cube::labels::Labels;
+use rs_cube::{Renderer, Scene, Theme, Turn};
+use std::fs;
+use std::path::PathBuf;
+
+const BLESS: &str = "RS_CUBE_BLESS";
+const WIDTH: u16 = 60;
+const HEIGHT: u16 = 24;
+
+// The plain text a frame writes, followed by a grid of its colours
+fn snapshot(renderer: &mut Renderer, scene: &Scene) -> String {
+    let theme = renderer.theme.clone();
+    let frame = renderer.render(scene);
+    let mut out = Vec::new();
+    frame.write_plain(&mut out, false).unwrap();
+    out.push(b'\n');
+
+    for (chars, colors) in frame
+        .chars
+        .chunks(frame.width as usize)
+        .zip(frame.colors.chunks(frame.width as usize))
+    {
+        let row: String = chars
+            .iter()
+            .zip(colors)
+            .map(
+                |(&val, &color)| match theme.faces.iter().position(|&c| c == color) {
+                    _ if val == ' ' => ' ',
+                    Some(face) => Face::ALL[face].to_char(),
+                    None if color == theme.body => 'k',
+                    None => '?',
+                },
+            )
+            .collect();
+        out.extend_from_slice(row.trim_end().as_bytes());
+        out.push(b'\n');
+    }
+    String::from_utf8(out).unwrap()
+}
+
+fn check(name: &str, renderer: &mut Renderer, scene: &Scene) {
+    let actual = snapshot(renderer, scene);
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name]
+        .iter()
+        .collect::<PathBuf>()
+        .with_extension("txt");
+
+    if std::env::var_os(BLESS).is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "{}: {}, run with {}=1 to write it",
+            path.display(),
+            err,
+            BLESS
+        )
+    });
+    if actual != expected {
+        let line = actual
+            .lines()
+            .zip(expected.lines())
+            .position(|(a, e)| a != e)
+            .unwrap_or(actual.lines().count().min(expected.lines().count()));
+        panic!(
+            "{} differs from {} from line {}, run with {}=1 if the change is intended\n\
+             expected:\n{}\nactual:\n{}",
+            name,
+            path.display(),
+            line + 1,
+            BLESS,
+            expected,
+            actual
+        );
+    }
+}
+
+fn renderer() -> Renderer {
+    Renderer::new(WIDTH, HEIGHT, 1, Theme::default())
+}
+
+#[test]
+fn solved() {
+    check("solved", &mut renderer(), &scene(""));
+}
+
+#[test]
+fn scrambled() {
+    check(
+        "scrambled",
+        &mut renderer(),
+        &scene("R U R' U' F2 D' L B2 M x"),
+    );
+}
+
+#[test]
+fn turning_layer() {
+    let mut scene = scene("R U");
+    scene.turn = Some(Turn {
+        axis: 1,
+        layers: 0b100,
+        angle: 0.5,
+    });
+    check("turning_layer", &mut renderer(), &scene);
+}
+
+#[test]
+fn isometric() {
+    let mut scene = scene("F R");
+    scene.camera.projection = Projection::Isometric;
+    scene.angles = scene.camera.fixed_angles().unwrap();
+    check("isometric", &mut renderer(), &scene);
+}
+
+#[test]
+fn close_perspective() {
+    let mut scene = scene("");
+    scene.camera.distance = 6.0;
+    check("close_perspective", &mut renderer(), &scene);
+}
+
+#[test]
+fn antialiased_edges_and_labels() {
+    let mut renderer = Renderer::new(WIDTH, HEIGHT, 2, Theme::default());
+    renderer.edges = true;
+    renderer.labels = Some(Labels::Letters);
+    check("antialiased_edges_and_labels", &mut renderer, &scene("R U"));
+}
+
+#[test]
+fn per_pixel_lighting() {
+    let mut scene = scene("");
+    scene.lighting.per_pixel = true;
+    scene.lighting.ambient = 0.1;
+    scene.lighting.specular = 0.6;
+    check("per_pixel_lighting", &mut renderer(), &scene);
+}
+
+#[test]
+fn stickerless() {
+    let theme = Theme::builtin("stickerless").unwrap();
+    let mut renderer = Renderer::new(WIDTH, HEIGHT, 1, theme);
+    check("stickerless", &mut renderer, &scene("R U"));
+}
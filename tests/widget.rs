@@ -0,0 +1,153 @@
+// Renders the cube widget into ratatui buffers and compares the characters and colours with
+// snapshots, one letter per face colour and k for the black plastic
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::widgets::{StatefulWidget, Widget};
+mod common;
+
+use rs_cube::{CubeWidget, Renderer, Scene, Theme};
+
+const CHARS: [&str; 18] = [
+    "                                        ",
+    "                                        ",
+    "                                        ",
+    "               ,;;!#$$$*;               ",
+    "         -;;*$$#$$$$##$$$$;-            ",
+    "         -!*$$#$$##$$$$$$#$$$;-         ",
+    "         ,,,~!$$$$$$*$$$$$$$$$$*,       ",
+    "         ,,,,,,;#$$$$$$#$$$#!~~,.       ",
+    "         ,,,,,,,~;*$$$!!;,,.....        ",
+    "         ,,,,,,,,,,;~~........,.        ",
+    "        .,,~,,,-,,,,....,.,....         ",
+    "        ..,,~~,,,~~...,........         ",
+    "          ..,,~,,,,....-.,....          ",
+    "            ..,,,,,..,......            ",
+    "              .,,,......                ",
+    "                .,...                   ",
+    "                                        ",
+    "                                        ",
+];
+
+const COLORS: [&str; 18] = [
+    "                                        ",
+    "                                        ",
+    "                                        ",
+    "               kkOkkOOOOO               ",
+    "         kkOOOkkOOOOkkkkBBBk            ",
+    "         kkkOOOOWkkBBBBkBBBBkBk         ",
+    "         kBBkkWWWWWkkBkkBBBBBBBkk       ",
+    "         kBBkRkkWkkWWWkBBBBBkkkkY       ",
+    "         BBBkRRRkkkWWWWkkkkYYYYY        ",
+    "         BBBkkRRRRRkkkBBkYYYkYkk        ",
+    "        kWWkRRRkRRRkBBBBkYkYYYY         ",
+    "        kWWWRRRkRRkkBBkkYYYkYkk         ",
+    "          kkRRRkRRRkBBBkYkkYYY          ",
+    "            kRRRRkkkBkBYYYkY            ",
+    "              kRRRkBBBkY                ",
+    "                kkkBk                   ",
+    "                                        ",
+    "                                        ",
+];
+
+const AREA: Rect = Rect::new(0, 0, 40, 18);
+
+fn scene() -> Scene {
+    let mut scene = common::scene("R U");
+    scene.camera.distance = 5.0;
+    scene
+}
+
+fn chars(buf: &Buffer, area: Rect) -> Vec<String> {
+    area.rows()
+        .map(|row| row.columns().map(|pos| buf[pos].symbol()).collect())
+        .collect()
+}
+
+fn colors(buf: &Buffer, area: Rect) -> Vec<String> {
+    area.rows()
+        .map(|row| {
+            row.columns()
+                .map(|pos| match buf[pos].fg {
+                    Color::Reset => ' ',
+                    Color::Black => 'k',
+                    Color::Gray => 'W',
+                    Color::Red => 'R',
+                    Color::Green => 'G',
+                    Color::Yellow => 'Y',
+                    Color::LightRed => 'O',
+                    Color::Blue => 'B',
+                    _ => '?',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn renders_the_scene_into_the_buffer() {
+    let mut buf = Buffer::empty(AREA);
+    let mut renderer = Renderer::new(1, 1, 2, Theme::default());
+    StatefulWidget::render(CubeWidget::new(&scene()), AREA, &mut buf, &mut renderer);
+
+    assert_eq!(chars(&buf, AREA), CHARS);
+    assert_eq!(colors(&buf, AREA), COLORS);
+}
+
+#[test]
+fn stays_inside_its_area() {
+    let mut buf = Buffer::empty(Rect::new(0, 0, 60, 24));
+    let area = Rect::new(10, 3, 40, 18);
+    let mut renderer = Renderer::new(1, 1, 2, Theme::default());
+    StatefulWidget::render(CubeWidget::new(&scene()), area, &mut buf, &mut renderer);
+
+    assert_eq!(chars(&buf, area), CHARS);
+    assert_eq!(colors(&buf, area), COLORS);
+    for pos in buf.area.positions().filter(|&pos| !area.contains(pos)) {
+        assert_eq!(buf[pos], Cell::EMPTY, "cell {:?} outside the area", pos);
+    }
+}
+
+#[test]
+fn leaves_uncovered_cells_alone() {
+    let mut buf = Buffer::filled(AREA, Cell::new("x"));
+    let mut renderer = Renderer::new(1, 1, 2, Theme::default());
+    StatefulWidget::render(CubeWidget::new(&scene()), AREA, &mut buf, &mut renderer);
+
+    for (row, expected) in chars(&buf, AREA).iter().zip(CHARS) {
+        let expected = expected.replace(' ', "x");
+        assert_eq!(row, &expected);
+    }
+}
+
+#[test]
+fn follows_the_area_size() {
+    let mut renderer = Renderer::new(1, 1, 2, Theme::default());
+    let scene = scene();
+    let mut small = Buffer::empty(Rect::new(0, 0, 20, 8));
+    StatefulWidget::render(
+        CubeWidget::new(&scene),
+        small.area,
+        &mut small,
+        &mut renderer,
+    );
+    assert_eq!((renderer.width(), renderer.height()), (20, 8));
+
+    let mut buf = Buffer::empty(AREA);
+    StatefulWidget::render(CubeWidget::new(&scene), AREA, &mut buf, &mut renderer);
+    assert_eq!(chars(&buf, AREA), CHARS);
+}
+
+#[test]
+fn plain_widget_matches_a_default_renderer() {
+    let scene = scene();
+    let mut plain = Buffer::empty(AREA);
+    Widget::render(CubeWidget::new(&scene), AREA, &mut plain);
+
+    let mut stateful = Buffer::empty(AREA);
+    let mut renderer = Renderer::new(AREA.width, AREA.height, 1, Theme::default());
+    StatefulWidget::render(CubeWidget::new(&scene), AREA, &mut stateful, &mut renderer);
+
+    assert_eq!(plain, stateful);
+    assert_ne!(plain, Buffer::empty(AREA));
+}